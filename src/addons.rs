@@ -0,0 +1,291 @@
+use crate::CurseForge;
+use crate::definitions::{FingerprintFuzzyMatch, FolderFingerprint, GetFuzzyMatchesRequestBody};
+use crate::error::Result;
+use crate::fingerprint::fingerprint_file;
+use crate::identify::list_files;
+use crate::ids::GameId;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Client flavor suffixes allowed after the folder name in a TOC file name.
+const TOC_FLAVORS: [&str; 10] = [
+    "mainline", "classic", "vanilla", "bcc", "tbc", "wrath", "wotlkc", "cata", "mists", "era",
+];
+
+#[derive(Debug, Clone)]
+pub struct AddonFolder {
+    pub path: PathBuf,
+    pub fingerprint: FolderFingerprint,
+    /// The CurseForge mod sharing the most file fingerprints with this folder, if any.
+    pub matched: Option<FingerprintFuzzyMatch>,
+}
+
+impl CurseForge {
+    /// Fingerprints every addon folder directly under `root` and looks them up among the files
+    /// of `game_id` with the fuzzy matching endpoint.
+    pub async fn identify_addon_folders(
+        &self,
+        root: impl AsRef<Path>,
+        game_id: GameId,
+    ) -> Result<Vec<AddonFolder>> {
+        let root = root.as_ref().to_path_buf();
+        let folders = crate::blocking(move || fingerprint_addon_folders(&root)).await?;
+        if folders.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let body = GetFuzzyMatchesRequestBody {
+            game_id,
+            fingerprints: folders.iter().map(|(_, folder)| folder.clone()).collect(),
+        };
+        let matches = self
+            .get_fingerprints_fuzzy_matches_by_game_id(game_id, &body)
+            .await?
+            .data
+            .fuzzy_matches;
+
+        Ok(folders
+            .into_iter()
+            .map(|(path, fingerprint)| {
+                let matched = best_match(&fingerprint, &matches).cloned();
+                AddonFolder {
+                    path,
+                    fingerprint,
+                    matched,
+                }
+            })
+            .collect())
+    }
+}
+
+fn best_match<'a>(
+    folder: &FolderFingerprint,
+    matches: &'a [FingerprintFuzzyMatch],
+) -> Option<&'a FingerprintFuzzyMatch> {
+    matches
+        .iter()
+        .map(|m| {
+            let shared = m
+                .fingerprints
+                .iter()
+                .filter(|fingerprint| folder.fingerprints.contains(fingerprint))
+                .count();
+            (shared, m)
+        })
+        .filter(|(shared, _)| *shared > 0)
+        .max_by_key(|(shared, _)| *shared)
+        .map(|(_, m)| m)
+}
+
+/// Fingerprints every addon folder directly under `root`, folders without a TOC file are skipped.
+pub fn fingerprint_addon_folders(root: &Path) -> io::Result<Vec<(PathBuf, FolderFingerprint)>> {
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && let Some(fingerprint) = folder_fingerprint(&entry.path())?
+        {
+            folders.push((entry.path(), fingerprint));
+        }
+    }
+    folders.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(folders)
+}
+
+/// Fingerprints the files of an addon folder the way the CurseForge client does.
+///
+/// Only the files that are part of the addon are included: its TOC files, `Bindings.xml`,
+/// every file listed in a TOC file and every file pulled by an XML `Script` or `Include` tag,
+/// recursively. Paths are resolved case-insensitively. Returns `None` if the folder has no
+/// TOC file.
+pub fn folder_fingerprint(folder: &Path) -> io::Result<Option<FolderFingerprint>> {
+    let Some(foldername) = folder.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let files = files_by_key(folder)?;
+
+    let mut pending: Vec<String> = files
+        .keys()
+        .filter(|path| !path.contains('/'))
+        .filter(|path| is_toc_file(foldername, path) || *path == "bindings.xml")
+        .cloned()
+        .collect();
+    if !pending.iter().any(|path| path.ends_with(".toc")) {
+        return Ok(None);
+    }
+
+    let mut included = BTreeSet::new();
+    while let Some(path) = pending.pop() {
+        let Some(real_path) = files.get(&path) else {
+            continue;
+        };
+        if !included.insert(path.clone()) {
+            continue;
+        }
+
+        let referenced = if path.ends_with(".toc") {
+            toc_references(&String::from_utf8_lossy(&fs::read(real_path)?))
+        } else if path.ends_with(".xml") {
+            xml_references(&String::from_utf8_lossy(&fs::read(real_path)?))
+        } else {
+            continue;
+        };
+        let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        pending.extend(
+            referenced
+                .iter()
+                .filter_map(|reference| resolve(dir, reference)),
+        );
+    }
+
+    let fingerprints = included
+        .iter()
+        .map(|path| fingerprint_file(&files[path]).map(i64::from))
+        .collect::<io::Result<_>>()?;
+    Ok(Some(FolderFingerprint {
+        foldername: foldername.to_string(),
+        fingerprints,
+    }))
+}
+
+/// The files of `folder`, keyed by lowercase `/`-separated relative path.
+fn files_by_key(folder: &Path) -> io::Result<HashMap<String, PathBuf>> {
+    Ok(list_files(folder)?
+        .into_iter()
+        .filter_map(|path| {
+            let key = path
+                .strip_prefix(folder)
+                .ok()?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+                .collect::<Vec<_>>()
+                .join("/");
+            Some((key, path))
+        })
+        .collect())
+}
+
+fn is_toc_file(foldername: &str, path: &str) -> bool {
+    let Some(stem) = path.strip_suffix(".toc") else {
+        return false;
+    };
+    let foldername = foldername.to_lowercase();
+    match stem.strip_prefix(&foldername) {
+        Some("") => true,
+        Some(suffix) => suffix
+            .strip_prefix(['-', '_'])
+            .is_some_and(|flavor| TOC_FLAVORS.contains(&flavor)),
+        None => false,
+    }
+}
+
+/// Files listed in a TOC file, one per line, ignoring comments and `##` directives.
+fn toc_references(toc: &str) -> Vec<String> {
+    toc.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Files pulled by the `file` attribute of `Script` and `Include` tags, outside of comments.
+fn xml_references(xml: &str) -> Vec<String> {
+    let mut references = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            rest = comment.split_once("-->").map_or("", |(_, after)| after);
+            continue;
+        }
+
+        let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        if !["script", "include"].contains(&tag[..name_end].to_lowercase().as_str()) {
+            continue;
+        }
+        if let Some(file) = attribute(&tag[name_end..], "file") {
+            references.push(file.to_string());
+        }
+    }
+    references
+}
+
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    loop {
+        let start = rest.find(|c: char| !c.is_whitespace() && c != '/')?;
+        rest = &rest[start..];
+        let (key, after) = rest.split_once('=')?;
+        let after = after.trim_start();
+        let quote = after.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, after) = after[1..].split_once(quote)?;
+        if key.trim().eq_ignore_ascii_case(name) {
+            return Some(value);
+        }
+        rest = after;
+    }
+}
+
+/// Resolves a `\` or `/` separated reference relative to `dir`, as a lowercase key.
+fn resolve(dir: &str, reference: &str) -> Option<String> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    for part in reference.split(['\\', '/']) {
+        match part.trim() {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/").to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_from_toc_and_xml() {
+        let toc = "## Interface: 110000\n# comment\n\nLibs\\embeds.xml\r\nCore.lua\n";
+        assert_eq!(toc_references(toc), ["Libs\\embeds.xml", "Core.lua"]);
+
+        let xml = r#"<Ui><Script file="a.lua"/><!-- <Include file="skipped.xml"/> -->
+            <Include file='Lib\lib.xml' /><Frame file="not-a-script.lua"/></Ui>"#;
+        assert_eq!(xml_references(xml), ["a.lua", "Lib\\lib.xml"]);
+
+        assert_eq!(resolve("libs", "..\\Core.LUA").as_deref(), Some("core.lua"));
+        assert_eq!(resolve("", "../outside.lua"), None);
+    }
+
+    #[test]
+    fn toc_file_names() {
+        assert!(is_toc_file("MyAddon", "myaddon.toc"));
+        assert!(is_toc_file("MyAddon", "myaddon_classic.toc"));
+        assert!(is_toc_file("MyAddon", "myaddon-mainline.toc"));
+        assert!(!is_toc_file("MyAddon", "otheraddon.toc"));
+        assert!(!is_toc_file("MyAddon", "myaddon_extra.toc"));
+    }
+
+    #[test]
+    fn reads_latin1_toc() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("MyAddon");
+        fs::create_dir(&folder).unwrap();
+        fs::write(
+            folder.join("MyAddon.toc"),
+            b"## Title-deDE: Gr\xfc\xdfe\nCore.lua\n",
+        )
+        .unwrap();
+        fs::write(folder.join("Core.lua"), b"print('hi')").unwrap();
+
+        let fingerprint = folder_fingerprint(&folder).unwrap().unwrap();
+        assert_eq!(fingerprint.foldername, "MyAddon");
+        assert_eq!(fingerprint.fingerprints.len(), 2);
+    }
+}
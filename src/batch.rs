@@ -0,0 +1,119 @@
+use crate::CurseForge;
+use crate::definitions::{File, GetModFilesRequestBody, GetModsRequestBody, Mod};
+use crate::error::Result;
+use crate::ids::{FileId, ModId};
+use crate::pagination::CONCURRENT_REQUESTS;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::hash::Hash;
+
+/// Number of ids sent in a single `get_mods` or `get_files` request.
+const IDS_PER_REQUEST: usize = 500;
+
+/// Result of a lookup of many ids.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch<I, T> {
+    /// Returned items, in the order of their ids in the input, once per id.
+    pub found: Vec<T>,
+    /// Ids the API returned nothing for, in input order.
+    pub missing: Vec<I>,
+}
+
+impl CurseForge {
+    /// Fetches any number of mods, in chunks of ids small enough for the API.
+    pub async fn get_mods_all(&self, mod_ids: &[ModId]) -> Result<Batch<ModId, Mod>> {
+        fetch_all(
+            mod_ids,
+            |m: &Mod| m.id,
+            |mod_ids| async move {
+                let body = GetModsRequestBody {
+                    mod_ids,
+                    filter_pc_only: None,
+                };
+                Ok(self.get_mods(&body).await?.data)
+            },
+        )
+        .await
+    }
+
+    /// Fetches any number of files, in chunks of ids small enough for the API.
+    pub async fn get_files_all(&self, file_ids: &[FileId]) -> Result<Batch<FileId, File>> {
+        fetch_all(
+            file_ids,
+            |f: &File| f.id,
+            |file_ids| async move {
+                let body = GetModFilesRequestBody { file_ids };
+                Ok(self.get_files(&body).await?.data)
+            },
+        )
+        .await
+    }
+}
+
+/// Looks up the distinct `ids` in concurrent chunks and orders the results like the input.
+async fn fetch_all<I, T, F, Fut>(ids: &[I], id_of: fn(&T) -> I, fetch: F) -> Result<Batch<I, T>>
+where
+    I: Copy + Eq + Hash,
+    F: Fn(Vec<I>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let mut seen = HashSet::new();
+    let ids: Vec<I> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+
+    let chunks: Vec<Vec<I>> = ids.chunks(IDS_PER_REQUEST).map(<[I]>::to_vec).collect();
+    let chunks: Vec<Vec<T>> = futures::stream::iter(chunks)
+        .map(&fetch)
+        .buffered(CONCURRENT_REQUESTS)
+        .try_collect()
+        .await?;
+    let mut items: HashMap<I, T> = chunks
+        .into_iter()
+        .flatten()
+        .map(|item| (id_of(&item), item))
+        .collect();
+
+    let mut batch = Batch {
+        found: Vec::with_capacity(items.len()),
+        missing: Vec::new(),
+    };
+    for id in ids {
+        match items.remove(&id) {
+            Some(item) => batch.found.push(item),
+            None => batch.missing.push(id),
+        }
+    }
+    Ok(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn chunks_and_orders() {
+        let requests = AtomicUsize::new(0);
+        let ids: Vec<i32> = (0..1200).rev().chain([5, 5]).collect();
+        let batch = fetch_all(
+            &ids,
+            |id: &i32| *id,
+            |chunk| {
+                requests.fetch_add(1, Ordering::Relaxed);
+                assert!(chunk.len() <= IDS_PER_REQUEST);
+                // Returns the even ids only, in reverse order.
+                async move { Ok(chunk.into_iter().rev().filter(|id| id % 2 == 0).collect()) }
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(requests.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            batch.found,
+            (0..1200).rev().filter(|id| id % 2 == 0).collect::<Vec<_>>()
+        );
+        assert_eq!(batch.missing.len(), 600);
+        assert_eq!(batch.missing[0], 1199);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::cache::{Cache, CacheConfig, Caches, MemoryCache};
+use crate::definitions::{CF_URL, CF_V2_URL};
+use crate::disk_cache::{DiskCache, DiskCacheConfig};
+use crate::error::{Error, Result};
+use crate::rate_limit::{RateLimit, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::{ClientConfig, CurseForge};
+use reqwest::Proxy;
+use reqwest::header::HeaderValue;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Configures and creates a [`CurseForge`] client.
+///
+/// Connection-level settings (`connect_timeout`, `read_timeout`, `proxy`) are baked into the
+/// `reqwest::Client`, so `build` fails if they are combined with a client supplied with
+/// [`CurseForgeBuilder::client`]. Changing them on a builder from [`CurseForge::to_builder`]
+/// creates a new client, with its own connection pool.
+#[derive(Debug, Default)]
+pub struct CurseForgeBuilder {
+    api_key: Option<String>,
+    base_url: Option<String>,
+    base_url_v2: Option<String>,
+    connection: Connection,
+    /// Whether a connection-level setting was changed on this builder.
+    connection_changed: bool,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limit: Option<RateLimit>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    caches: Caches,
+    client: Option<reqwest::Client>,
+    /// Client of the client this builder derives from, if it was built from `connection`.
+    inherited_client: Option<reqwest::Client>,
+}
+
+/// Settings the `reqwest::Client` is built with.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Connection {
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+}
+
+impl Connection {
+    fn build(self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map_err(|err| Error::Config(format!("Unable to create CurseForge client: {err}")))
+    }
+}
+
+impl CurseForgeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn from_client(cf: &CurseForge) -> Self {
+        let config = &cf.config;
+        let (client, inherited_client) = match config.connection {
+            Some(_) => (None, Some(cf.client.clone())),
+            None => (Some(cf.client.clone()), None),
+        };
+        Self {
+            api_key: config.api_key.to_str().ok().map(str::to_string),
+            base_url: Some(config.base_url.clone()),
+            base_url_v2: Some(config.base_url_v2.clone()),
+            timeout: config.timeout,
+            user_agent: config
+                .user_agent
+                .as_ref()
+                .and_then(|user_agent| user_agent.to_str().ok())
+                .map(str::to_string),
+            retry_policy: Some(config.retry_policy.clone()),
+            rate_limiter: cf.rate_limiter.clone(),
+            caches: cf.caches.clone(),
+            connection: config.connection.clone().unwrap_or_default(),
+            client,
+            inherited_client,
+            ..Self::default()
+        }
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Base URL of the v1 API, defaults to [`CF_URL`].
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Base URL of the v2 API, defaults to [`CF_V2_URL`].
+    pub fn base_url_v2(mut self, base_url_v2: impl Into<String>) -> Self {
+        self.base_url_v2 = Some(base_url_v2.into());
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.connect_timeout = Some(timeout);
+        self.connection_changed()
+    }
+
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.connection.read_timeout = Some(timeout);
+        self.connection_changed()
+    }
+
+    /// Total timeout of a request, from connecting until the response body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.connection.proxy = Some(proxy);
+        self.connection_changed()
+    }
+
+    /// The inherited client no longer matches the settings, a new one has to be built.
+    fn connection_changed(mut self) -> Self {
+        self.connection_changed = true;
+        self.inherited_client = None;
+        self
+    }
+
+    /// Retry failed requests according to `policy`, requests are not retried by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Throttle requests on the client side so the server never has to.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Keep responses in memory, see [`CacheConfig`] for the TTL of each endpoint. Responses are
+    /// not cached by default.
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.caches.memory = Some(Arc::new(MemoryCache::new(config)));
+        self
+    }
+
+    /// Keep responses on disk so they survive restarts and can be served when the API is
+    /// unreachable, see [`DiskCache`].
+    pub fn disk_cache(mut self, config: DiskCacheConfig) -> Self {
+        self.caches.disk = Some(Arc::new(DiskCache::new(config)));
+        self
+    }
+
+    /// Keep responses in a custom store, consulted after the in-memory and on-disk caches.
+    pub fn cache_backend(mut self, cache: impl Cache + 'static) -> Self {
+        self.caches.custom = Some(Arc::new(cache));
+        self
+    }
+
+    /// Use a pre-built `reqwest::Client` instead of creating a new one.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn build(self) -> Result<CurseForge> {
+        let api_key = self
+            .api_key
+            .ok_or_else(|| Error::Config("Missing CURSE_FORGE_API_KEY value".to_string()))?;
+        let mut api_key = HeaderValue::from_str(&api_key)
+            .map_err(|_| Error::Config("Invalid CURSE_FORGE_API_KEY value".to_string()))?;
+        api_key.set_sensitive(true);
+
+        let user_agent = self
+            .user_agent
+            .map(|user_agent| {
+                HeaderValue::from_str(&user_agent)
+                    .map_err(|_| Error::Config("Invalid user agent value".to_string()))
+            })
+            .transpose()?;
+
+        let rate_limiter = match self.rate_limit {
+            Some(limit) => Some(Arc::new(RateLimiter::new(&limit)?)),
+            None => self.rate_limiter,
+        };
+
+        let (client, connection) = match (self.client, self.inherited_client) {
+            (Some(_), _) if self.connection_changed => {
+                return Err(Error::Config(
+                    "Connection settings can't be applied to a supplied reqwest::Client"
+                        .to_string(),
+                ));
+            }
+            (Some(client), _) => (client, None),
+            (None, Some(client)) => (client, Some(self.connection)),
+            (None, None) => (self.connection.clone().build()?, Some(self.connection)),
+        };
+
+        Ok(CurseForge {
+            client,
+            config: Arc::new(ClientConfig {
+                api_key,
+                user_agent,
+                timeout: self.timeout,
+                connection,
+                retry_policy: self.retry_policy.unwrap_or_else(RetryPolicy::none),
+                base_url: trim_base_url(self.base_url.as_deref().unwrap_or(CF_URL)),
+                base_url_v2: trim_base_url(self.base_url_v2.as_deref().unwrap_or(CF_V2_URL)),
+            }),
+            rate_limiter,
+            caches: self.caches,
+        })
+    }
+}
+
+fn trim_base_url(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_connection_settings() {
+        let cf = CurseForge::new("key").unwrap();
+        let derived = cf.to_builder().user_agent("agent").build().unwrap();
+        assert_eq!(
+            derived.config.connection.as_ref().unwrap().connect_timeout,
+            None
+        );
+
+        // A new client is built with the inherited and the changed settings.
+        let derived = cf
+            .to_builder()
+            .read_timeout(Duration::from_secs(5))
+            .build()
+            .unwrap()
+            .to_builder()
+            .connect_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let connection = derived.config.connection.as_ref().unwrap();
+        assert_eq!(connection.read_timeout, Some(Duration::from_secs(5)));
+        assert_eq!(connection.connect_timeout, Some(Duration::from_secs(1)));
+
+        let supplied = CurseForge::builder()
+            .api_key("key")
+            .client(reqwest::Client::new())
+            .build()
+            .unwrap();
+        assert!(supplied.config.connection.is_none());
+        assert!(supplied.to_builder().user_agent("agent").build().is_ok());
+        let result = supplied
+            .to_builder()
+            .connect_timeout(Duration::from_secs(1))
+            .build();
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+}
@@ -0,0 +1,361 @@
+use crate::disk_cache::DiskCache;
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Groups of API endpoints sharing a cache TTL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    Games,
+    Versions,
+    VersionTypes,
+    Categories,
+    Search,
+    Mods,
+    FeaturedMods,
+    ModDescriptions,
+    Files,
+    Changelogs,
+    DownloadUrls,
+    Fingerprints,
+    MinecraftVersions,
+    MinecraftModLoaders,
+}
+
+impl Endpoint {
+    /// How long responses are kept by default: long for taxonomy that rarely changes, short for
+    /// searches.
+    pub fn default_ttl(self) -> Duration {
+        match self {
+            Self::Games
+            | Self::Versions
+            | Self::VersionTypes
+            | Self::Categories
+            | Self::MinecraftVersions
+            | Self::MinecraftModLoaders => 24 * HOUR,
+            Self::Mods
+            | Self::Files
+            | Self::ModDescriptions
+            | Self::Changelogs
+            | Self::DownloadUrls
+            | Self::Fingerprints => 10 * MINUTE,
+            Self::FeaturedMods => 5 * MINUTE,
+            Self::Search => MINUTE,
+        }
+    }
+}
+
+/// A store of serialized API responses, consulted before every request.
+///
+/// Keys identify a request by its method, URL and body, and values are the raw JSON bodies.
+/// Implement it to back the client with a shared store such as Redis, failures should be
+/// treated as misses since the cache is only an optimization.
+#[async_trait]
+pub trait Cache: Debug + Send + Sync {
+    /// Returns the response stored under `key`, if any.
+    async fn get(&self, key: &str, endpoint: Endpoint) -> Option<CachedResponse>;
+
+    async fn put(&self, key: &str, endpoint: Endpoint, body: Bytes);
+
+    /// Drops the responses of `endpoint`, or every response if `None`.
+    async fn invalidate(&self, endpoint: Option<Endpoint>);
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub body: Bytes,
+    pub freshness: Freshness,
+}
+
+/// How a stored response may be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// Past its TTL, served marked as stale while it is refreshed in the background.
+    Revalidate,
+    /// Only served, marked as stale, when the API is unreachable.
+    Expired,
+}
+
+/// Settings of the in-memory response cache.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// Maximum number of responses kept, the least recently used ones are evicted first.
+    pub max_entries: usize,
+    /// Maximum total size of the kept response bodies.
+    pub max_bytes: usize,
+    /// TTL overrides, a zero TTL disables caching for the endpoint.
+    pub ttls: HashMap<Endpoint, Duration>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1024,
+            max_bytes: 64 * 1024 * 1024,
+            ttls: HashMap::new(),
+        }
+    }
+}
+
+impl CacheConfig {
+    pub fn ttl(mut self, endpoint: Endpoint, ttl: Duration) -> Self {
+        self.ttls.insert(endpoint, ttl);
+        self
+    }
+
+    fn ttl_for(&self, endpoint: Endpoint) -> Duration {
+        self.ttls
+            .get(&endpoint)
+            .copied()
+            .unwrap_or_else(|| endpoint.default_ttl())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+/// Responses kept in memory until their TTL, evicting the least recently used ones.
+#[derive(Debug)]
+pub struct MemoryCache {
+    config: CacheConfig,
+    inner: Mutex<Inner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// Keys by last use, oldest first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    bytes: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    endpoint: Endpoint,
+    body: Bytes,
+    expires_at: Instant,
+    used_at: u64,
+}
+
+impl MemoryCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the fresh response stored under `key`, counting a hit or a miss.
+    fn lookup(&self, key: &str, endpoint: Endpoint) -> Option<Bytes> {
+        if self.config.ttl_for(endpoint).is_zero() {
+            return None;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        let body = match inner.entries.get(key) {
+            Some(entry) if entry.expires_at > now => Some(entry.body.clone()),
+            Some(_) => {
+                inner.remove(key);
+                None
+            }
+            None => None,
+        };
+        match &body {
+            Some(_) => {
+                inner.touch(key);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        body
+    }
+
+    fn insert(&self, key: String, endpoint: Endpoint, body: Bytes) {
+        let ttl = self.config.ttl_for(endpoint);
+        if ttl.is_zero() || body.len() > self.config.max_bytes {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.remove(&key);
+        inner.bytes += body.len();
+        inner.tick += 1;
+        let used_at = inner.tick;
+        inner.recency.insert(used_at, key.clone());
+        inner.entries.insert(
+            key,
+            Entry {
+                endpoint,
+                body,
+                expires_at: Instant::now() + ttl,
+                used_at,
+            },
+        );
+
+        while inner.entries.len() > self.config.max_entries || inner.bytes > self.config.max_bytes {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.remove(&oldest);
+        }
+    }
+
+    fn remove_endpoint(&self, endpoint: Option<Endpoint>) {
+        let mut inner = self.inner.lock().unwrap();
+        let keys: Vec<String> = inner
+            .entries
+            .iter()
+            .filter(|(_, entry)| endpoint.is_none_or(|endpoint| entry.endpoint == endpoint))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            inner.remove(&key);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: inner.entries.len(),
+            bytes: inner.bytes,
+        }
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str, endpoint: Endpoint) -> Option<CachedResponse> {
+        self.lookup(key, endpoint).map(|body| CachedResponse {
+            body,
+            freshness: Freshness::Fresh,
+        })
+    }
+
+    async fn put(&self, key: &str, endpoint: Endpoint, body: Bytes) {
+        self.insert(key.to_string(), endpoint, body);
+    }
+
+    async fn invalidate(&self, endpoint: Option<Endpoint>) {
+        self.remove_endpoint(endpoint);
+    }
+}
+
+/// The caches of a client, from the fastest to the slowest.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Caches {
+    pub(crate) memory: Option<Arc<MemoryCache>>,
+    pub(crate) disk: Option<Arc<DiskCache>>,
+    pub(crate) custom: Option<Arc<dyn Cache>>,
+}
+
+impl Caches {
+    pub(crate) fn layers(&self) -> Vec<&dyn Cache> {
+        let memory = self.memory.as_deref().map(|cache| cache as &dyn Cache);
+        let disk = self.disk.as_deref().map(|cache| cache as &dyn Cache);
+        memory
+            .into_iter()
+            .chain(disk)
+            .chain(self.custom.as_deref())
+            .collect()
+    }
+}
+
+impl Inner {
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.used_at);
+            self.bytes -= entry.body.len();
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            let key = self
+                .recency
+                .remove(&entry.used_at)
+                .unwrap_or_else(|| key.to_string());
+            entry.used_at = tick;
+            self.recency.insert(tick, key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(content: &'static str) -> Bytes {
+        Bytes::from_static(content.as_bytes())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expires_per_endpoint() {
+        let cache = MemoryCache::new(CacheConfig::default());
+        cache.insert("games".to_string(), Endpoint::Games, body("games"));
+        cache.insert("search".to_string(), Endpoint::Search, body("search"));
+
+        tokio::time::advance(2 * MINUTE).await;
+        assert!(cache.lookup("games", Endpoint::Games).is_some());
+        assert!(cache.lookup("search", Endpoint::Search).is_none());
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1,
+                bytes: 5
+            }
+        );
+
+        cache.remove_endpoint(Some(Endpoint::Games));
+        assert!(cache.lookup("games", Endpoint::Games).is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let config = CacheConfig {
+            max_entries: 2,
+            max_bytes: 10,
+            ..Default::default()
+        }
+        .ttl(Endpoint::Search, Duration::ZERO);
+        let cache = MemoryCache::new(config);
+        cache.insert("a".to_string(), Endpoint::Mods, body("aaaa"));
+        cache.insert("b".to_string(), Endpoint::Mods, body("bbbb"));
+        assert!(cache.lookup("a", Endpoint::Mods).is_some());
+        cache.insert("c".to_string(), Endpoint::Mods, body("cccc"));
+        assert!(cache.lookup("b", Endpoint::Mods).is_none());
+        assert!(cache.lookup("a", Endpoint::Mods).is_some());
+
+        cache.insert("d".to_string(), Endpoint::Mods, body("dddddddd"));
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.lookup("d", Endpoint::Mods).is_some());
+
+        cache.insert("e".to_string(), Endpoint::Search, body("e"));
+        assert!(cache.lookup("e", Endpoint::Search).is_none());
+    }
+}
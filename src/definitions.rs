@@ -1,10 +1,13 @@
+use crate::ids::{AuthorId, CategoryId, ClassId, FileId, GameId, GameVersionTypeId, ModId};
 use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::collections::HashMap;
 
 pub const CF_URL: &str = "https://api.curseforge.com/v1";
 pub const CF_V2_URL: &str = "https://api.curseforge.com/v2";
+pub const CF_CDN_URL: &str = "https://edge.forgecdn.net/files";
 
 // ============================================================================
 // ENUMS
@@ -183,7 +186,7 @@ pub struct GameAssets {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Game {
-    pub id: i32,
+    pub id: GameId,
     pub name: String,
     pub slug: String,
     pub date_modified: DateTime<Utc>,
@@ -204,7 +207,7 @@ pub struct GameVersion {
 #[serde(rename_all = "camelCase")]
 pub struct GameVersionsByTypeV1 {
     #[serde(rename = "type")]
-    pub version_type: i32,
+    pub version_type: GameVersionTypeId,
     pub versions: Vec<String>,
 }
 
@@ -212,15 +215,15 @@ pub struct GameVersionsByTypeV1 {
 #[serde(rename_all = "camelCase")]
 pub struct GameVersionsByType {
     #[serde(rename = "type")]
-    pub version_type: i32,
+    pub version_type: GameVersionTypeId,
     pub versions: Vec<GameVersion>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameVersionType {
-    pub id: i32,
-    pub game_id: i32,
+    pub id: GameVersionTypeId,
+    pub game_id: GameId,
     pub name: String,
     pub slug: String,
     pub is_syncable: bool,
@@ -230,16 +233,16 @@ pub struct GameVersionType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Category {
-    pub id: i32,
-    pub game_id: i32,
+    pub id: CategoryId,
+    pub game_id: GameId,
     pub name: String,
     pub slug: String,
     pub url: Option<String>,
     pub icon_url: Option<String>,
     pub date_modified: DateTime<Utc>,
     pub is_class: Option<bool>,
-    pub class_id: Option<i32>,
-    pub parent_category_id: Option<i32>,
+    pub class_id: Option<ClassId>,
+    pub parent_category_id: Option<CategoryId>,
     pub display_index: Option<i32>,
 }
 
@@ -259,7 +262,7 @@ pub struct ModLinks {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModAuthor {
-    pub id: i32,
+    pub id: AuthorId,
     pub name: String,
     pub url: String,
 }
@@ -268,7 +271,7 @@ pub struct ModAuthor {
 #[serde(rename_all = "camelCase")]
 pub struct ModAsset {
     pub id: i32,
-    pub mod_id: i32,
+    pub mod_id: ModId,
     pub title: Option<String>,
     pub description: Option<String>,
     pub thumbnail_url: Option<String>,
@@ -282,13 +285,13 @@ pub struct SortableGameVersion {
     pub game_version_padded: String,
     pub game_version: String,
     pub game_version_release_date: DateTime<Utc>,
-    pub game_version_type_id: Option<i32>,
+    pub game_version_type_id: Option<GameVersionTypeId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileDependency {
-    pub mod_id: i32,
+    pub mod_id: ModId,
     pub relation_type: FileRelationType,
 }
 
@@ -310,19 +313,19 @@ pub struct FileModule {
 #[serde(rename_all = "camelCase")]
 pub struct FileIndex {
     pub game_version: String,
-    pub file_id: i32,
+    pub file_id: FileId,
     pub filename: String,
     pub release_type: FileReleaseType,
-    pub game_version_type_id: Option<i32>,
+    pub game_version_type_id: Option<GameVersionTypeId>,
     pub mod_loader: Option<ModLoaderType>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct File {
-    pub id: i32,
-    pub game_id: i32,
-    pub mod_id: i32,
+    pub id: FileId,
+    pub game_id: GameId,
+    pub mod_id: ModId,
     pub is_available: bool,
     pub display_name: Option<String>,
     pub file_name: Option<String>,
@@ -338,10 +341,10 @@ pub struct File {
     pub sortable_game_versions: Vec<SortableGameVersion>,
     pub dependencies: Vec<FileDependency>,
     pub expose_as_alternative: Option<bool>,
-    pub parent_project_file_id: Option<i32>,
-    pub alternate_file_id: Option<i32>,
+    pub parent_project_file_id: Option<FileId>,
+    pub alternate_file_id: Option<FileId>,
     pub is_server_pack: Option<bool>,
-    pub server_pack_file_id: Option<i32>,
+    pub server_pack_file_id: Option<FileId>,
     pub is_early_access_content: Option<bool>,
     pub early_access_end_date: Option<DateTime<Utc>>,
     pub file_fingerprint: i64,
@@ -351,8 +354,8 @@ pub struct File {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Mod {
-    pub id: i32,
-    pub game_id: i32,
+    pub id: ModId,
+    pub game_id: GameId,
     pub name: String,
     pub slug: String,
     pub links: ModLinks,
@@ -360,13 +363,13 @@ pub struct Mod {
     pub status: ModStatus,
     pub download_count: i64,
     pub is_featured: bool,
-    pub primary_category_id: i32,
+    pub primary_category_id: CategoryId,
     pub categories: Vec<Category>,
-    pub class_id: Option<i32>,
+    pub class_id: Option<ClassId>,
     pub authors: Vec<ModAuthor>,
     pub logo: Option<ModAsset>,
     pub screenshots: Vec<ModAsset>,
-    pub main_file_id: i32,
+    pub main_file_id: FileId,
     pub latest_files: Vec<File>,
     pub latest_files_indexes: Vec<FileIndex>,
     pub latest_early_access_files_indexes: Vec<FileIndex>,
@@ -402,7 +405,7 @@ pub struct FolderFingerprint {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FingerprintMatch {
-    pub id: i32,
+    pub id: ModId,
     pub file: File,
     pub latest_files: Vec<File>,
 }
@@ -410,7 +413,7 @@ pub struct FingerprintMatch {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FingerprintFuzzyMatch {
-    pub id: i32,
+    pub id: ModId,
     pub file: File,
     pub latest_files: Vec<File>,
     pub fingerprints: Vec<i64>,
@@ -448,7 +451,7 @@ pub struct MinecraftGameVersion {
     pub json_download_url: String,
     pub approved: bool,
     pub date_modified: DateTime<Utc>,
-    pub game_version_type_id: i32,
+    pub game_version_type_id: GameVersionTypeId,
     pub game_version_status: GameVersionStatus,
     pub game_version_type_status: GameVersionTypeStatus,
 }
@@ -486,11 +489,11 @@ pub struct MinecraftModLoaderVersion {
     pub minecraft_version: String,
     pub additional_files_json: Option<String>,
     pub mod_loader_game_version_id: i32,
-    pub mod_loader_game_version_type_id: i32,
+    pub mod_loader_game_version_type_id: GameVersionTypeId,
     pub mod_loader_game_version_status: GameVersionStatus,
     pub mod_loader_game_version_type_status: GameVersionTypeStatus,
     pub mc_game_version_id: i32,
-    pub mc_game_version_type_id: i32,
+    pub mc_game_version_type_id: GameVersionTypeId,
     pub mc_game_version_status: GameVersionStatus,
     pub mc_game_version_type_status: GameVersionTypeStatus,
     pub install_profile_json: Option<String>,
@@ -512,9 +515,9 @@ pub struct GetGamesParams {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetCategoriesParams {
-    pub game_id: i32,
+    pub game_id: GameId,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub class_id: Option<i32>,
+    pub class_id: Option<ClassId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub classes_only: Option<bool>,
 }
@@ -522,11 +525,11 @@ pub struct GetCategoriesParams {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchModsParams {
-    pub game_id: i32,
+    pub game_id: GameId,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub class_id: Option<i32>,
+    pub class_id: Option<ClassId>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub category_id: Option<i32>,
+    pub category_id: Option<CategoryId>,
     /// Filter by a list of category ids (overrides categoryId). Format: [1,2,3...]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub category_ids: Option<String>,
@@ -547,11 +550,11 @@ pub struct SearchModsParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mod_loader_types: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub game_version_type_id: Option<i32>,
+    pub game_version_type_id: Option<GameVersionTypeId>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub author_id: Option<i32>,
+    pub author_id: Option<AuthorId>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub primary_author_id: Option<i32>,
+    pub primary_author_id: Option<AuthorId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub slug: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -568,7 +571,7 @@ pub struct GetModFilesParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mod_loader_type: Option<ModLoaderType>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub game_version_type_id: Option<i32>,
+    pub game_version_type_id: Option<GameVersionTypeId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub index: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -609,9 +612,9 @@ pub struct GetMinecraftModLoadersParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetFeaturedModsRequestBody {
-    pub game_id: i32,
-    pub excluded_mod_ids: Vec<i32>,
-    pub game_version_type_id: Option<i32>,
+    pub game_id: GameId,
+    pub excluded_mod_ids: Vec<ModId>,
+    pub game_version_type_id: Option<GameVersionTypeId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -623,20 +626,20 @@ pub struct GetFingerprintMatchesRequestBody {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetFuzzyMatchesRequestBody {
-    pub game_id: i32,
+    pub game_id: GameId,
     pub fingerprints: Vec<FolderFingerprint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetModFilesRequestBody {
-    pub file_ids: Vec<i32>,
+    pub file_ids: Vec<FileId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetModsRequestBody {
-    pub mod_ids: Vec<i32>,
+    pub mod_ids: Vec<ModId>,
     pub filter_pc_only: Option<bool>,
 }
 
@@ -644,105 +647,185 @@ pub struct GetModsRequestBody {
 // API RESPONSE STRUCTS
 // ============================================================================
 
+/// Responses of the API, `stale` is set on the ones served from the disk cache past their TTL.
+pub(crate) trait ApiResponse: DeserializeOwned {
+    fn mark_stale(&mut self);
+}
+
+macro_rules! api_responses {
+    ($($response:ty),* $(,)?) => {
+        $(
+            impl ApiResponse for $response {
+                fn mark_stale(&mut self) {
+                    self.stale = true;
+                }
+            }
+        )*
+    };
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StringResponse {
     pub data: String,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetMinecraftVersionsResponse {
     pub data: Vec<MinecraftGameVersion>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetMinecraftModLoadersResponse {
     pub data: Vec<MinecraftModLoaderIndex>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetMinecraftVersionResponse {
     pub data: MinecraftGameVersion,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetMinecraftModLoaderResponse {
     pub data: MinecraftModLoaderVersion,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetCategoriesResponse {
     pub data: Vec<Category>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetGameResponse {
     pub data: Game,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetGamesResponse {
     pub data: Vec<Game>,
     pub pagination: Pagination,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetVersionTypesResponse {
     pub data: Vec<GameVersionType>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetVersionsResponseV1 {
     pub data: Vec<GameVersionsByTypeV1>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetVersionsResponse {
     pub data: Vec<GameVersionsByType>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetModResponse {
     pub data: Mod,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetModsResponse {
     pub data: Vec<Mod>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchModsResponse {
     pub data: Vec<Mod>,
     pub pagination: Pagination,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetFeaturedModsResponse {
     pub data: FeaturedModsResponse,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetModFileResponse {
     pub data: File,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetModFilesResponse {
     pub data: Vec<File>,
     pub pagination: Pagination,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetFilesResponse {
     pub data: Vec<File>,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetFingerprintMatchesResponse {
     pub data: FingerprintsMatchesResult,
+    #[serde(skip)]
+    pub stale: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetFingerprintsFuzzyMatchesResponse {
     pub data: FingerprintFuzzyMatchResult,
-}
+    #[serde(skip)]
+    pub stale: bool,
+}
+
+api_responses!(
+    StringResponse,
+    GetMinecraftVersionsResponse,
+    GetMinecraftModLoadersResponse,
+    GetMinecraftVersionResponse,
+    GetMinecraftModLoaderResponse,
+    GetCategoriesResponse,
+    GetGameResponse,
+    GetGamesResponse,
+    GetVersionTypesResponse,
+    GetVersionsResponseV1,
+    GetVersionsResponse,
+    GetModResponse,
+    GetModsResponse,
+    SearchModsResponse,
+    GetFeaturedModsResponse,
+    GetModFileResponse,
+    GetModFilesResponse,
+    GetFilesResponse,
+    GetFingerprintMatchesResponse,
+    GetFingerprintsFuzzyMatchesResponse,
+);
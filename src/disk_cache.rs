@@ -0,0 +1,297 @@
+use crate::cache::{Cache, CachedResponse, Endpoint, Freshness};
+use crate::error::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+const ENTRY_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+/// Age past which a temporary file is considered left behind by an interrupted write.
+const STALE_TEMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Settings of the on-disk response cache.
+#[derive(Debug, Clone)]
+pub struct DiskCacheConfig {
+    /// Directory the responses are stored in, created if missing.
+    pub dir: PathBuf,
+    /// TTL overrides, defaults to [`Endpoint::default_ttl`]. A zero TTL makes every response of
+    /// the endpoint revalidated, the stored one is then only used as a fallback.
+    pub ttls: HashMap<Endpoint, Duration>,
+    /// How long past its TTL a response is still served while it is refreshed in the background.
+    pub stale_while_revalidate: Duration,
+}
+
+impl DiskCacheConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttls: HashMap::new(),
+            stale_while_revalidate: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    pub fn ttl(mut self, endpoint: Endpoint, ttl: Duration) -> Self {
+        self.ttls.insert(endpoint, ttl);
+        self
+    }
+}
+
+/// Responses stored on disk, one file per request named after its endpoint and the hash of the
+/// request.
+///
+/// Stored responses outlive their TTL: when the API can't be reached they are served marked as
+/// stale, whatever their age. Use [`DiskCache::prune`] to bound the size of the directory.
+#[derive(Debug)]
+pub struct DiskCache {
+    config: DiskCacheConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub removed: usize,
+    pub remaining: usize,
+    pub remaining_bytes: u64,
+}
+
+impl DiskCache {
+    pub fn new(config: DiskCacheConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.config.dir
+    }
+
+    /// Removes the responses older than `max_age`, then the oldest ones until the directory
+    /// holds at most `max_bytes`. Temporary files left behind by interrupted writes are removed
+    /// too, they are not counted in the stats.
+    pub async fn prune(
+        &self,
+        max_age: Option<Duration>,
+        max_bytes: Option<u64>,
+    ) -> Result<PruneStats> {
+        let dir = self.config.dir.clone();
+        Ok(crate::blocking(move || prune(&dir, max_age, max_bytes)).await?)
+    }
+
+    /// Removes every stored response.
+    pub async fn clear(&self) -> Result<PruneStats> {
+        self.prune(Some(Duration::ZERO), None).await
+    }
+
+    fn entry_path(&self, key: &str, endpoint: Endpoint) -> PathBuf {
+        let hash = hex::encode(Sha1::digest(key.as_bytes()));
+        self.config
+            .dir
+            .join(format!("{endpoint:?}-{hash}.{ENTRY_EXTENSION}"))
+    }
+}
+
+#[async_trait]
+impl Cache for DiskCache {
+    async fn get(&self, key: &str, endpoint: Endpoint) -> Option<CachedResponse> {
+        let path = self.entry_path(key, endpoint);
+        let age = tokio::fs::metadata(&path)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|modified| modified.elapsed().unwrap_or_default())?;
+        let body = tokio::fs::read(&path).await.ok()?;
+
+        let ttl = self
+            .config
+            .ttls
+            .get(&endpoint)
+            .copied()
+            .unwrap_or_else(|| endpoint.default_ttl());
+        let freshness = if age < ttl {
+            Freshness::Fresh
+        } else if age < ttl + self.config.stale_while_revalidate {
+            Freshness::Revalidate
+        } else {
+            Freshness::Expired
+        };
+        Some(CachedResponse {
+            body: Bytes::from(body),
+            freshness,
+        })
+    }
+
+    /// Failures are ignored as the cache is only an optimization.
+    async fn put(&self, key: &str, endpoint: Endpoint, body: Bytes) {
+        let path = self.entry_path(key, endpoint);
+        let temp = path.with_extension(format!("{}.{TEMP_EXTENSION}", fastrand::u64(..)));
+        let written = async {
+            tokio::fs::create_dir_all(&self.config.dir).await?;
+            tokio::fs::write(&temp, &body).await?;
+            tokio::fs::rename(&temp, &path).await
+        };
+        if written.await.is_err() {
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+    }
+
+    async fn invalidate(&self, endpoint: Option<Endpoint>) {
+        let dir = self.config.dir.clone();
+        let prefix = endpoint
+            .map(|endpoint| format!("{endpoint:?}-"))
+            .unwrap_or_default();
+        let _ = crate::blocking(move || remove_entries(&dir, &prefix)).await;
+    }
+}
+
+fn remove_entries(dir: &Path, prefix: &str) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let matches = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with(prefix));
+        if matches && path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn prune(dir: &Path, max_age: Option<Duration>, max_bytes: Option<u64>) -> io::Result<PruneStats> {
+    let mut entries = Vec::new();
+    match fs::read_dir(dir) {
+        Ok(read_dir) => {
+            for entry in read_dir {
+                let entry = entry?;
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION) {
+                    let metadata = entry.metadata()?;
+                    entries.push((metadata.modified()?, metadata.len(), path));
+                } else if path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+                    let age = entry.metadata()?.modified()?.elapsed().unwrap_or_default();
+                    if age >= STALE_TEMP_AGE {
+                        remove_file(&path)?;
+                    }
+                }
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    // Newest first, so the oldest entries are the ones dropped by the size limit.
+    entries.sort_by_key(|(modified, ..)| std::cmp::Reverse(*modified));
+
+    let now = SystemTime::now();
+    let mut stats = PruneStats::default();
+    for (modified, len, path) in entries {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let too_old = max_age.is_some_and(|max_age| age >= max_age);
+        let too_big = max_bytes.is_some_and(|max_bytes| stats.remaining_bytes + len > max_bytes);
+        if too_old || too_big {
+            if remove_file(&path)? {
+                stats.removed += 1;
+            }
+        } else {
+            stats.remaining += 1;
+            stats.remaining_bytes += len;
+        }
+    }
+    Ok(stats)
+}
+
+/// Removes a file, returning whether it still existed.
+fn remove_file(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_and_prunes() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache =
+            DiskCache::new(DiskCacheConfig::new(dir.path()).ttl(Endpoint::Search, Duration::ZERO));
+        assert!(cache.get("a", Endpoint::Mods).await.is_none());
+
+        cache
+            .put("a", Endpoint::Mods, Bytes::from_static(b"aaaa"))
+            .await;
+        cache
+            .put("b", Endpoint::Mods, Bytes::from_static(b"bbbbbb"))
+            .await;
+        cache
+            .put("s", Endpoint::Search, Bytes::from_static(b""))
+            .await;
+        let cached = cache.get("a", Endpoint::Mods).await.unwrap();
+        assert_eq!(&cached.body[..], b"aaaa");
+        assert_eq!(cached.freshness, Freshness::Fresh);
+        let cached = cache.get("s", Endpoint::Search).await.unwrap();
+        assert_eq!(cached.freshness, Freshness::Revalidate);
+
+        cache.invalidate(Some(Endpoint::Search)).await;
+        assert!(cache.get("s", Endpoint::Search).await.is_none());
+
+        let old = SystemTime::now() - Duration::from_secs(60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(cache.entry_path("a", Endpoint::Mods))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        cache
+            .put("c", Endpoint::Mods, Bytes::from_static(b"cc"))
+            .await;
+
+        let stats = cache.prune(None, Some(8)).await.unwrap();
+        assert_eq!(
+            stats,
+            PruneStats {
+                removed: 1,
+                remaining: 2,
+                remaining_bytes: 8
+            }
+        );
+        assert!(cache.get("a", Endpoint::Mods).await.is_none());
+
+        let stats = cache.clear().await.unwrap();
+        assert_eq!(stats.remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn prunes_stale_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(DiskCacheConfig::new(dir.path()));
+        cache
+            .put("a", Endpoint::Mods, Bytes::from_static(b"aaaa"))
+            .await;
+        let stale = dir.path().join("Mods-a.1.tmp");
+        let writing = dir.path().join("Mods-a.2.tmp");
+        fs::write(&stale, b"aa").unwrap();
+        fs::write(&writing, b"aa").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&stale)
+            .unwrap()
+            .set_modified(SystemTime::now() - STALE_TEMP_AGE)
+            .unwrap();
+
+        let stats = cache.prune(None, None).await.unwrap();
+        assert_eq!(
+            stats,
+            PruneStats {
+                removed: 0,
+                remaining: 1,
+                remaining_bytes: 4
+            }
+        );
+        assert!(!stale.exists());
+        assert!(writing.exists());
+    }
+}
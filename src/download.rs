@@ -0,0 +1,708 @@
+use crate::CurseForge;
+use crate::definitions::{CF_CDN_URL, File, HashAlgo, Mod};
+use crate::error::{Error, IntegrityCheck, Result};
+use crate::fingerprint::fingerprint_file;
+use crate::ids::{FileId, ModId};
+use futures::{StreamExt, TryStreamExt, stream};
+use md5::Md5;
+use reqwest::header::RANGE;
+use reqwest::{StatusCode, Url};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    /// Expected size in bytes, when known.
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    pub length: u64,
+    pub fingerprint: u32,
+}
+
+impl CurseForge {
+    /// Downloads `file` to `dest`, see [`CurseForge::download_file_with_progress`].
+    pub async fn download_file(
+        &self,
+        file: &File,
+        dest: impl AsRef<Path>,
+    ) -> Result<DownloadedFile> {
+        self.download_file_with_progress(file, dest, |_| {}).await
+    }
+
+    /// Downloads `file` to `dest`, calling `on_progress` every time a chunk is written.
+    ///
+    /// The body is streamed to a `.part` file next to `dest` which is only renamed once its
+    /// length, hashes and fingerprint match the ones of `file`, so an interrupted or corrupt
+    /// download never ends up at `dest`. Files without a download URL are located with
+    /// [`CurseForge::resolve_download`] and fail with [`Error::ManualDownloadRequired`] when they
+    /// can't be downloaded automatically.
+    pub async fn download_file_with_progress(
+        &self,
+        file: &File,
+        dest: impl AsRef<Path>,
+        on_progress: impl FnMut(DownloadProgress) + Send,
+    ) -> Result<DownloadedFile> {
+        let dest = dest.as_ref();
+        let result = self.download_to(file, dest, false, on_progress).await;
+        if result.is_err() {
+            let _ = fs::remove_file(part_path(dest)).await;
+        }
+        result.map(|(downloaded, _)| downloaded)
+    }
+
+    /// Downloads many files with bounded concurrency, one failure does not stop the others.
+    ///
+    /// Files already present at their destination with matching hashes are skipped and
+    /// partial downloads left by a previous run are resumed with HTTP range requests. The
+    /// outcomes are returned in the order of `downloads`.
+    pub async fn download_files(
+        &self,
+        downloads: Vec<(File, PathBuf)>,
+        options: &BulkDownloadOptions,
+    ) -> Vec<BulkDownload> {
+        stream::iter(downloads)
+            .map(|(file, dest)| async move {
+                let outcome = self.download_or_skip(&file, &dest, options).await;
+                BulkDownload {
+                    mod_id: file.mod_id,
+                    file_id: file.id,
+                    dest,
+                    outcome,
+                }
+            })
+            .buffered(options.concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    async fn download_or_skip(
+        &self,
+        file: &File,
+        dest: &Path,
+        options: &BulkDownloadOptions,
+    ) -> DownloadOutcome {
+        if options.skip_existing
+            && fs::try_exists(dest).await.unwrap_or(false)
+            && let Ok(digests) = hash_path(dest).await
+            && digests.verify(file).is_ok()
+        {
+            return DownloadOutcome::Skipped(DownloadedFile {
+                path: dest.to_path_buf(),
+                length: digests.length,
+                fingerprint: digests.fingerprint,
+            });
+        }
+
+        match self.download_to(file, dest, options.resume, |_| {}).await {
+            Ok((downloaded, true)) => DownloadOutcome::Resumed(downloaded),
+            Ok((downloaded, false)) => DownloadOutcome::Downloaded(downloaded),
+            Err(err) => DownloadOutcome::Failed(err),
+        }
+    }
+
+    /// Streams `file` into its `.part` file, resuming it if asked to, then verifies and renames
+    /// it to `dest`. The part file is kept on transport errors so it can be resumed later.
+    async fn download_to(
+        &self,
+        file: &File,
+        dest: &Path,
+        resume: bool,
+        mut on_progress: impl FnMut(DownloadProgress) + Send,
+    ) -> Result<(DownloadedFile, bool)> {
+        let location = self.resolve_download(file).await?;
+        let Some(url) = location.url() else {
+            return Err(Error::ManualDownloadRequired {
+                mod_id: file.mod_id,
+                file_id: file.id,
+            });
+        };
+        let part = part_path(dest);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let offset = match fs::metadata(&part).await {
+            Ok(metadata) if resume => metadata.len(),
+            _ => 0,
+        };
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request.send().await?;
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            response = self.client.get(url).send().await?;
+        }
+        if !response.status().is_success() {
+            return Err(Error::from_response(response).await);
+        }
+
+        let resumed = offset > 0 && response.status() == StatusCode::PARTIAL_CONTENT;
+        let (mut out, mut hasher) = if resumed {
+            let hasher = hash_part(&part).await?;
+            let out = fs::OpenOptions::new().append(true).open(&part).await?;
+            (out, hasher)
+        } else {
+            (fs::File::create(&part).await?, FileHasher::default())
+        };
+
+        let total = response
+            .content_length()
+            .map(|len| len + hasher.length)
+            .or_else(|| u64::try_from(file.file_length).ok().filter(|len| *len > 0));
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.try_next().await? {
+            out.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            on_progress(DownloadProgress {
+                downloaded: hasher.length,
+                total,
+            });
+        }
+        out.sync_all().await?;
+        drop(out);
+
+        let fingerprint = fingerprint_path(&part).await?;
+        let digests = hasher.finish(fingerprint);
+        if let Err(err) = digests.verify(file) {
+            let _ = fs::remove_file(&part).await;
+            return Err(err);
+        }
+        fs::rename(&part, dest).await?;
+
+        let downloaded = DownloadedFile {
+            path: dest.to_path_buf(),
+            length: digests.length,
+            fingerprint,
+        };
+        Ok((downloaded, resumed))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkDownloadOptions {
+    /// Maximum number of files downloaded at the same time.
+    pub concurrency: usize,
+    /// Resume partial downloads left by a previous run.
+    pub resume: bool,
+    /// Skip files already present at their destination with matching hashes.
+    pub skip_existing: bool,
+}
+
+impl Default for BulkDownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            resume: true,
+            skip_existing: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct BulkDownload {
+    pub mod_id: ModId,
+    pub file_id: FileId,
+    pub dest: PathBuf,
+    pub outcome: DownloadOutcome,
+}
+
+#[derive(Debug)]
+pub enum DownloadOutcome {
+    Downloaded(DownloadedFile),
+    /// Downloaded by completing a partial download.
+    Resumed(DownloadedFile),
+    /// Already present with matching hashes.
+    Skipped(DownloadedFile),
+    Failed(Error),
+}
+
+impl DownloadOutcome {
+    pub fn is_success(&self) -> bool {
+        !matches!(self, Self::Failed(_))
+    }
+}
+
+/// Where a file can be downloaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DownloadLocation {
+    /// The download URL returned by the API.
+    Api(String),
+    /// The CDN URL built from the file id and name, used when the API returns none.
+    Cdn(String),
+    /// The project disallows third-party distribution or the file name is unknown, the user has
+    /// to get the file from the mod's page.
+    Manual {
+        mod_id: ModId,
+        file_id: FileId,
+        website_url: Option<String>,
+    },
+}
+
+impl DownloadLocation {
+    /// Finds the best location to download `file` of `project` from: the API URL if there is
+    /// one, otherwise the CDN URL unless the project disallows third-party distribution or the
+    /// file name is unknown, in which case the user has to download it.
+    pub fn of(file: &File, project: &Mod) -> Self {
+        if let Some(url) = &file.download_url {
+            return Self::Api(url.clone());
+        }
+        match &file.file_name {
+            Some(file_name) if project.allow_mod_distribution != Some(false) => {
+                Self::Cdn(cdn_url(file.id, file_name))
+            }
+            _ => Self::Manual {
+                mod_id: file.mod_id,
+                file_id: file.id,
+                website_url: project.links.website_url.clone(),
+            },
+        }
+    }
+
+    /// The URL to download from, `None` for manual downloads.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::Api(url) | Self::Cdn(url) => Some(url),
+            Self::Manual { .. } => None,
+        }
+    }
+}
+
+impl CurseForge {
+    /// Finds the best location to download `file` from, see [`DownloadLocation::of`].
+    ///
+    /// The mod is only fetched when the API returned no download URL for the file.
+    pub async fn resolve_download(&self, file: &File) -> Result<DownloadLocation> {
+        if let Some(url) = &file.download_url {
+            return Ok(DownloadLocation::Api(url.clone()));
+        }
+        let project = self.get_mod(file.mod_id).await?.data;
+        Ok(DownloadLocation::of(file, &project))
+    }
+}
+
+/// Builds the CDN URL of a file, e.g. `https://edge.forgecdn.net/files/4571/7/mod.jar` for the
+/// file `4571007` named `mod.jar`.
+pub fn cdn_url(file_id: FileId, file_name: &str) -> String {
+    let mut url = Url::parse(CF_CDN_URL).expect("CF_CDN_URL is a valid URL");
+    url.path_segments_mut()
+        .expect("CF_CDN_URL can be a base")
+        .extend([
+            (file_id.0 / 1000).to_string(),
+            (file_id.0 % 1000).to_string(),
+            file_name.to_string(),
+        ]);
+    url.into()
+}
+
+/// Path of the temporary file a download to `dest` is written to.
+pub(crate) fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Hashes a complete file already on disk.
+pub(crate) async fn hash_path(path: &Path) -> Result<FileDigests> {
+    let hasher = hash_part(path).await?;
+    let fingerprint = fingerprint_path(path).await?;
+    Ok(hasher.finish(fingerprint))
+}
+
+async fn hash_part(path: &Path) -> Result<FileHasher> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = FileHasher::default();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(hasher);
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+pub(crate) async fn fingerprint_path(path: &Path) -> Result<u32> {
+    let path = path.to_path_buf();
+    Ok(crate::blocking(move || fingerprint_file(path)).await?)
+}
+
+#[derive(Default)]
+pub(crate) struct FileHasher {
+    length: u64,
+    sha1: Sha1,
+    md5: Md5,
+}
+
+impl FileHasher {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        self.sha1.update(data);
+        self.md5.update(data);
+    }
+
+    pub(crate) fn finish(self, fingerprint: u32) -> FileDigests {
+        FileDigests {
+            length: self.length,
+            sha1: hex::encode(self.sha1.finalize()),
+            md5: hex::encode(self.md5.finalize()),
+            fingerprint,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FileDigests {
+    pub(crate) length: u64,
+    pub(crate) sha1: String,
+    pub(crate) md5: String,
+    pub(crate) fingerprint: u32,
+}
+
+impl FileDigests {
+    /// Checks the digests against the ones advertised by the API, unknown values are skipped.
+    pub(crate) fn verify(&self, file: &File) -> Result<()> {
+        if file.file_length > 0 && self.length != file.file_length as u64 {
+            return Err(mismatch(
+                IntegrityCheck::Length,
+                file.file_length,
+                self.length,
+            ));
+        }
+        for hash in &file.hashes {
+            let (check, actual) = match hash.algo {
+                HashAlgo::Sha1 => (IntegrityCheck::Sha1, &self.sha1),
+                HashAlgo::Md5 => (IntegrityCheck::Md5, &self.md5),
+            };
+            if !hash.value.eq_ignore_ascii_case(actual) {
+                return Err(mismatch(check, &hash.value, actual));
+            }
+        }
+        if file.file_fingerprint != 0 && i64::from(self.fingerprint) != file.file_fingerprint {
+            return Err(mismatch(
+                IntegrityCheck::Fingerprint,
+                file.file_fingerprint,
+                self.fingerprint,
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn mismatch(
+    check: IntegrityCheck,
+    expected: impl ToString,
+    actual: impl ToString,
+) -> Error {
+    Error::Integrity {
+        check,
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::compute_fingerprint;
+    use crate::fixtures;
+    use crate::fixtures::{Response, TestServer, UNREACHABLE_URL};
+    use serde_json::json;
+
+    const CONTENT: &[u8] = b"fn main() {\n    println!(\"hello\");\n}\n";
+
+    /// A file as advertised by the API for `content`, downloaded from `url`.
+    fn file(content: &[u8], url: &str) -> File {
+        fixtures::file(json!({
+            "hashes": [
+                { "value": hex::encode(Sha1::digest(content)), "algo": 1 },
+                { "value": hex::encode(Md5::digest(content)), "algo": 2 },
+            ],
+            "fileLength": content.len(),
+            "downloadUrl": url,
+            "fileFingerprint": compute_fingerprint(content),
+        }))
+    }
+
+    fn digests(content: &[u8]) -> FileDigests {
+        let mut hasher = FileHasher::default();
+        hasher.update(content);
+        hasher.finish(compute_fingerprint(content))
+    }
+
+    /// Serves `body`, answering range requests if `ranges` is set.
+    fn serve(body: &'static [u8], ranges: bool) -> TestServer {
+        TestServer::start(move |request| {
+            let offset = request
+                .header("range")
+                .filter(|_| ranges)
+                .and_then(|range| {
+                    range
+                        .strip_prefix("bytes=")?
+                        .strip_suffix('-')?
+                        .parse()
+                        .ok()
+                });
+            match offset {
+                Some(offset) if offset >= body.len() => Response::new(416, ""),
+                Some(offset) => Response::new(206, &body[offset..]).header(
+                    "Content-Range",
+                    format!("bytes {offset}-{}/{}", body.len() - 1, body.len()),
+                ),
+                None => Response::new(200, body),
+            }
+        })
+    }
+
+    /// The range offset of every request received by `server`.
+    fn offsets(server: &TestServer) -> Vec<Option<String>> {
+        server
+            .requests()
+            .iter()
+            .map(|request| {
+                let range = request.header("range")?;
+                Some(
+                    range
+                        .trim_start_matches("bytes=")
+                        .trim_end_matches('-')
+                        .to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Downloads `CONTENT` in bulk after leaving `part` in its part file.
+    async fn bulk_download(
+        part: &[u8],
+        ranges: bool,
+    ) -> (DownloadOutcome, Vec<Option<String>>, Vec<u8>) {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("mod.jar");
+        std::fs::write(part_path(&dest), part).unwrap();
+        let server = serve(CONTENT, ranges);
+        let cf = CurseForge::new("key").unwrap();
+
+        let downloads = vec![(file(CONTENT, &server.url), dest.clone())];
+        let mut downloaded = cf
+            .download_files(downloads, &BulkDownloadOptions::default())
+            .await;
+        assert!(!part_path(&dest).exists());
+        let requests = offsets(&server);
+        let content = std::fs::read(&dest).unwrap_or_default();
+        (downloaded.remove(0).outcome, requests, content)
+    }
+
+    #[test]
+    fn verifies_digests() {
+        let mut expected = file(CONTENT, "");
+        let actual = digests(CONTENT);
+        assert!(actual.verify(&expected).is_ok());
+
+        for hash in &mut expected.hashes {
+            hash.value = hash.value.to_uppercase();
+        }
+        assert!(actual.verify(&expected).is_ok());
+
+        let other = digests(b"fn main() {}");
+        let check = |file: &File| match other.verify(file) {
+            Err(Error::Integrity { check, .. }) => Some(check),
+            _ => None,
+        };
+        assert_eq!(check(&expected), Some(IntegrityCheck::Length));
+        expected.file_length = 0;
+        assert_eq!(check(&expected), Some(IntegrityCheck::Sha1));
+        expected.hashes.remove(0);
+        assert_eq!(check(&expected), Some(IntegrityCheck::Md5));
+        expected.hashes.clear();
+        assert_eq!(check(&expected), Some(IntegrityCheck::Fingerprint));
+        expected.file_fingerprint = 0;
+        assert_eq!(check(&expected), None);
+    }
+
+    #[tokio::test]
+    async fn renames_part_once_verified() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("mods/mod.jar");
+        let cf = CurseForge::new("key").unwrap();
+        let url = serve(CONTENT, false).url;
+
+        let mut progress = Vec::new();
+        let downloaded = cf
+            .download_file_with_progress(&file(CONTENT, &url), &dest, |p| progress.push(p))
+            .await
+            .unwrap();
+        assert_eq!(downloaded.length, CONTENT.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), CONTENT);
+        assert!(!part_path(&dest).exists());
+        assert_eq!(progress.last().unwrap().downloaded, CONTENT.len() as u64);
+
+        let dest = dir.path().join("corrupt.jar");
+        let expected = file(b"something else entirely", &url);
+        let failed = cf.download_file(&expected, &dest).await;
+        assert!(matches!(failed, Err(Error::Integrity { .. })));
+        assert!(!dest.exists());
+        assert!(!part_path(&dest).exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_partial_downloads() {
+        let (outcome, requests, content) = bulk_download(&CONTENT[..10], true).await;
+        assert!(
+            matches!(outcome, DownloadOutcome::Resumed(_)),
+            "{outcome:?}"
+        );
+        assert_eq!(requests, [Some("10".to_string())]);
+        assert_eq!(content, CONTENT);
+
+        // The server ignores the range and sends the whole file.
+        let (outcome, requests, content) = bulk_download(&CONTENT[..10], false).await;
+        assert!(
+            matches!(outcome, DownloadOutcome::Downloaded(_)),
+            "{outcome:?}"
+        );
+        assert_eq!(requests, [Some("10".to_string())]);
+        assert_eq!(content, CONTENT);
+
+        // The part file is longer than the file, the download restarts from scratch.
+        let stale = [CONTENT, b"trailing garbage"].concat();
+        let (outcome, requests, content) = bulk_download(&stale, true).await;
+        assert!(
+            matches!(outcome, DownloadOutcome::Downloaded(_)),
+            "{outcome:?}"
+        );
+        assert_eq!(requests, [Some(stale.len().to_string()), None]);
+        assert_eq!(content, CONTENT);
+    }
+
+    #[tokio::test]
+    async fn skips_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = serve(CONTENT, true);
+        let url = &server.url;
+        let cf = CurseForge::new("key").unwrap();
+        let valid = dir.path().join("valid.jar");
+        let corrupt = dir.path().join("corrupt.jar");
+        std::fs::write(&valid, CONTENT).unwrap();
+        std::fs::write(&corrupt, b"corrupt").unwrap();
+
+        let downloads = vec![
+            (file(CONTENT, url), valid.clone()),
+            (file(CONTENT, url), corrupt.clone()),
+        ];
+        let downloaded = cf
+            .download_files(downloads.clone(), &BulkDownloadOptions::default())
+            .await;
+        assert!(matches!(downloaded[0].outcome, DownloadOutcome::Skipped(_)));
+        assert!(matches!(
+            downloaded[1].outcome,
+            DownloadOutcome::Downloaded(_)
+        ));
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(std::fs::read(&corrupt).unwrap(), CONTENT);
+
+        let options = BulkDownloadOptions {
+            skip_existing: false,
+            ..Default::default()
+        };
+        let downloaded = cf.download_files(downloads, &options).await;
+        assert!(
+            downloaded
+                .iter()
+                .all(|d| matches!(d.outcome, DownloadOutcome::Downloaded(_)))
+        );
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn cdn_urls() {
+        assert_eq!(
+            cdn_url(FileId(4571007), "jei-1.20.1-forge-15.2.0.27.jar"),
+            "https://edge.forgecdn.net/files/4571/7/jei-1.20.1-forge-15.2.0.27.jar"
+        );
+        assert_eq!(
+            cdn_url(FileId(123), "Some Mod #1.jar"),
+            "https://edge.forgecdn.net/files/0/123/Some%20Mod%20%231.jar"
+        );
+    }
+
+    /// A client whose `get_mod` returns the default project with `fields` replaced.
+    fn serve_project(fields: serde_json::Value) -> (CurseForge, TestServer) {
+        let body = serde_json::to_vec(&json!({"data": fixtures::project(fields)})).unwrap();
+        let server = TestServer::start(move |_| Response::new(200, body.clone()));
+        let cf = CurseForge::builder()
+            .api_key("key")
+            .base_url(&server.url)
+            .build()
+            .unwrap();
+        (cf, server)
+    }
+
+    fn paths(server: &TestServer) -> Vec<String> {
+        server.requests().into_iter().map(|r| r.path).collect()
+    }
+
+    #[tokio::test]
+    async fn resolves_api_urls_without_fetching_the_mod() {
+        let cf = CurseForge::builder()
+            .api_key("key")
+            .base_url(UNREACHABLE_URL)
+            .build()
+            .unwrap();
+        let location = cf
+            .resolve_download(&file(CONTENT, "https://example.com/mod.jar"))
+            .await
+            .unwrap();
+        assert_eq!(
+            location,
+            DownloadLocation::Api("https://example.com/mod.jar".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn resolves_cdn_urls_when_distribution_is_allowed() {
+        let (cf, server) = serve_project(json!({"allowModDistribution": null}));
+        let location = cf.resolve_download(&fixtures::file(json!({}))).await;
+        assert_eq!(
+            location.unwrap(),
+            DownloadLocation::Cdn(cdn_url(FileId(4571007), "mod.jar"))
+        );
+        assert_eq!(paths(&server), ["/mods/238222"]);
+    }
+
+    #[tokio::test]
+    async fn resolves_manual_downloads() {
+        let website_url = "https://www.curseforge.com/minecraft/mc-mods/mod";
+        let manual = DownloadLocation::Manual {
+            mod_id: ModId(238222),
+            file_id: FileId(4571007),
+            website_url: Some(website_url.to_string()),
+        };
+
+        let (cf, server) = serve_project(json!({
+            "allowModDistribution": false,
+            "links": {"websiteUrl": website_url},
+        }));
+        let file = fixtures::file(json!({}));
+        assert_eq!(cf.resolve_download(&file).await.unwrap(), manual);
+        assert_eq!(paths(&server), ["/mods/238222"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let result = cf.download_file(&file, dir.path().join("mod.jar")).await;
+        assert!(
+            matches!(result, Err(Error::ManualDownloadRequired { .. })),
+            "{result:?}"
+        );
+
+        // Without a file name there is no CDN URL either.
+        let (cf, _server) = serve_project(json!({"links": {"websiteUrl": website_url}}));
+        let file = fixtures::file(json!({"fileName": null}));
+        assert_eq!(cf.resolve_download(&file).await.unwrap(), manual);
+    }
+}
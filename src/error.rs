@@ -0,0 +1,179 @@
+use crate::ids::{FileId, ModId};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Response, StatusCode};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+const BODY_SNIPPET_LEN: usize = 512;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Resource not found: {url}")]
+    NotFound { url: String },
+    #[error("Unauthorized, the CurseForge API key is missing or invalid")]
+    Unauthorized,
+    #[error("Forbidden: {body}")]
+    Forbidden { body: String },
+    #[error("Rate limited by CurseForge")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("Request rejected with status {status}: {body}")]
+    Client { status: StatusCode, body: String },
+    #[error("CurseForge server error {status}: {body}")]
+    Server { status: StatusCode, body: String },
+    #[error("Transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("Unable to decode response at `{path}`: {source}")]
+    Decode {
+        path: String,
+        body_snippet: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("Invalid modpack: {0}")]
+    InvalidModpack(String),
+    #[error("Invalid lockfile: {0}")]
+    InvalidLockfile(String),
+    #[error("File {file_id} of mod {mod_id} can only be downloaded manually")]
+    ManualDownloadRequired { mod_id: ModId, file_id: FileId },
+    #[error("File {check} mismatch, expected {expected} but got {actual}")]
+    Integrity {
+        check: IntegrityCheck,
+        expected: String,
+        actual: String,
+    },
+    /// Failure of a request shared by several callers, see [`crate::loader::DataLoader`] and
+    /// [`Error::root`].
+    #[error(transparent)]
+    Shared(Arc<Error>),
+    /// The batched request of a [`crate::loader::DataLoader`] lookup was aborted, by a panic or
+    /// the shutdown of the runtime.
+    #[error("Batched lookup cancelled before completing")]
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityCheck {
+    Length,
+    Sha1,
+    Md5,
+    Fingerprint,
+}
+
+impl fmt::Display for IntegrityCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Length => "length",
+            Self::Sha1 => "SHA-1",
+            Self::Md5 => "MD5",
+            Self::Fingerprint => "fingerprint",
+        })
+    }
+}
+
+impl Error {
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::Unauthorized => Some(StatusCode::UNAUTHORIZED),
+            Self::Forbidden { .. } => Some(StatusCode::FORBIDDEN),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Self::Client { status, .. } | Self::Server { status, .. } => Some(*status),
+            Self::Transport(err) => err.status(),
+            Self::Shared(err) => err.status(),
+            Self::Decode { .. }
+            | Self::Config(_)
+            | Self::Io(_)
+            | Self::Zip(_)
+            | Self::InvalidModpack(_)
+            | Self::InvalidLockfile(_)
+            | Self::ManualDownloadRequired { .. }
+            | Self::Integrity { .. }
+            | Self::Cancelled => None,
+        }
+    }
+
+    /// The underlying error, unwrapping [`Error::Shared`] failures.
+    pub fn root(&self) -> &Error {
+        match self {
+            Self::Shared(err) => err.root(),
+            err => err,
+        }
+    }
+
+    /// Turns a non-success response into the matching error variant.
+    pub(crate) async fn from_response(response: Response) -> Self {
+        let status = response.status();
+        let url = response.url().to_string();
+        let retry_after = parse_retry_after(response.headers());
+        let body = response
+            .text()
+            .await
+            .map(|body| snippet(body.as_bytes()))
+            .unwrap_or_default();
+
+        match status {
+            StatusCode::NOT_FOUND => Self::NotFound { url },
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden { body },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited { retry_after },
+            status if status.is_server_error() => Self::Server { status, body },
+            status => Self::Client { status, body },
+        }
+    }
+
+    pub(crate) fn decode(err: serde_path_to_error::Error<serde_json::Error>, body: &[u8]) -> Self {
+        Self::Decode {
+            path: err.path().to_string(),
+            body_snippet: snippet(body),
+            source: err.into_inner(),
+        }
+    }
+}
+
+/// Reads a `Retry-After` header given either as delay seconds or as an HTTP date.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.to_utc() - chrono::Utc::now()).to_std().ok()
+}
+
+fn snippet(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(BODY_SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn retry_after_seconds_and_date() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(120)));
+
+        let date = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+        let delay = parse_retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+}
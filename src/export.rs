@@ -0,0 +1,325 @@
+use crate::CurseForge;
+use crate::definitions::Mod;
+use crate::download::part_path;
+use crate::error::Result;
+use crate::identify::{fingerprint_directory, list_files};
+use crate::ids::ModId;
+use crate::modpack::{
+    MANIFEST_FILE_NAME, MANIFEST_TYPE, MANIFEST_VERSION, ManifestFile, ManifestMinecraft,
+    ManifestModLoader, ModpackManifest,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+const MODLIST_FILE_NAME: &str = "modlist.html";
+const OVERRIDES: &str = "overrides";
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    /// Required, packs without a Minecraft version can't be installed.
+    pub minecraft_version: String,
+    pub mod_loaders: Vec<ManifestModLoader>,
+    /// Instance folders whose files are looked up on CurseForge, unmatched ones go to overrides.
+    pub content_folders: Vec<PathBuf>,
+    /// Instance files and folders copied to overrides as they are.
+    pub overrides: Vec<PathBuf>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            version: String::new(),
+            author: String::new(),
+            minecraft_version: String::new(),
+            mod_loaders: Vec::new(),
+            content_folders: ["mods", "resourcepacks", "shaderpacks"]
+                .map(PathBuf::from)
+                .to_vec(),
+            overrides: [
+                "config",
+                "defaultconfigs",
+                "kubejs",
+                "scripts",
+                "options.txt",
+            ]
+            .map(PathBuf::from)
+            .to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportReport {
+    pub manifest: ModpackManifest,
+    /// Instance files referenced by the manifest, relative to the instance folder.
+    pub matched: Vec<PathBuf>,
+    /// Instance files copied to overrides, relative to the instance folder.
+    pub overrides: Vec<PathBuf>,
+}
+
+impl CurseForge {
+    /// Exports an instance folder as a CurseForge modpack zip written to `output`.
+    ///
+    /// Files of the content folders are fingerprinted, the ones found on CurseForge are listed
+    /// in `manifest.json` and `modlist.html`, the others are copied to overrides along with the
+    /// override paths of `options`.
+    pub async fn export_modpack(
+        &self,
+        instance_dir: impl AsRef<Path>,
+        options: &ExportOptions,
+        output: impl AsRef<Path>,
+    ) -> Result<ExportReport> {
+        let mut report = ExportReport {
+            manifest: manifest(options, Vec::new()),
+            matched: Vec::new(),
+            overrides: Vec::new(),
+        };
+        // Refuse to write a pack that `read_manifest` would reject.
+        report.manifest.validate()?;
+
+        let instance_dir = instance_dir.as_ref().to_path_buf();
+        let folders: Vec<PathBuf> = options
+            .content_folders
+            .iter()
+            .map(|folder| instance_dir.join(folder))
+            .collect();
+        let content = crate::blocking(move || {
+            let mut content = Vec::new();
+            for folder in folders.iter().filter(|folder| folder.is_dir()) {
+                content.extend(fingerprint_directory(folder)?);
+            }
+            io::Result::Ok(content)
+        })
+        .await?;
+
+        let matches = self
+            .match_fingerprints(None, content.iter().map(|(_, f)| *f))
+            .await?;
+
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+        for (path, fingerprint) in content {
+            let relative = path
+                .strip_prefix(&instance_dir)
+                .unwrap_or(&path)
+                .to_path_buf();
+            match matches.get(&i64::from(fingerprint)) {
+                Some(found) => {
+                    if seen.insert(found.file.id) {
+                        files.push(ManifestFile {
+                            project_id: found.id,
+                            file_id: found.file.id,
+                            required: true,
+                        });
+                    }
+                    report.matched.push(relative);
+                }
+                None => report.overrides.push(relative),
+            }
+        }
+        report.manifest.files = files;
+
+        let mod_ids: Vec<ModId> = report.manifest.files.iter().map(|f| f.project_id).collect();
+        let mods: HashMap<ModId, Mod> = (self.get_mods_all(&mod_ids).await?.found)
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect();
+        let modlist = modlist(&report.manifest, &mods);
+
+        let output = output.as_ref().to_path_buf();
+        let manifest = report.manifest.clone();
+        let extra = options.overrides.clone();
+        let copied = report.overrides.clone();
+        report.overrides = crate::blocking(move || {
+            let written = write_pack(&output, &instance_dir, &manifest, &modlist, &copied, &extra);
+            if written.is_err() {
+                let _ = fs::remove_file(part_path(&output));
+            }
+            written
+        })
+        .await?;
+        Ok(report)
+    }
+}
+
+fn manifest(options: &ExportOptions, files: Vec<ManifestFile>) -> ModpackManifest {
+    ModpackManifest {
+        minecraft: ManifestMinecraft {
+            version: options.minecraft_version.clone(),
+            mod_loaders: options.mod_loaders.clone(),
+            recommended_ram: None,
+        },
+        manifest_type: MANIFEST_TYPE.to_string(),
+        manifest_version: MANIFEST_VERSION,
+        name: options.name.clone(),
+        version: options.version.clone(),
+        author: options.author.clone(),
+        files,
+        overrides: OVERRIDES.to_string(),
+    }
+}
+
+/// Renders the `modlist.html` listing every mod of the manifest.
+fn modlist(manifest: &ModpackManifest, mods: &HashMap<ModId, Mod>) -> String {
+    let mut html = String::from("<ul>\n");
+    for file in &manifest.files {
+        let Some(m) = mods.get(&file.project_id) else {
+            continue;
+        };
+        let url = m.links.website_url.as_deref().unwrap_or_default();
+        let author = m
+            .authors
+            .first()
+            .map(|author| format!(" (by {})", escape(&author.name)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}{}</a></li>\n",
+            escape(url),
+            escape(&m.name),
+            author
+        ));
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the pack zip through a temporary file, returns every path copied to overrides.
+fn write_pack(
+    output: &Path,
+    instance_dir: &Path,
+    manifest: &ModpackManifest,
+    modlist: &str,
+    unmatched: &[PathBuf],
+    extra: &[PathBuf],
+) -> Result<Vec<PathBuf>> {
+    let part = part_path(output);
+    let mut zip = ZipWriter::new(fs::File::create(&part)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(MANIFEST_FILE_NAME, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(manifest).map_err(io::Error::from)?)?;
+    zip.start_file(MODLIST_FILE_NAME, options)?;
+    zip.write_all(modlist.as_bytes())?;
+
+    let mut overrides = unmatched.to_vec();
+    for path in extra {
+        let full = instance_dir.join(path);
+        if full.is_dir() {
+            overrides.extend(
+                list_files(&full)?
+                    .into_iter()
+                    .filter_map(|file| Some(file.strip_prefix(instance_dir).ok()?.to_path_buf())),
+            );
+        } else if full.is_file() {
+            overrides.push(path.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    overrides.retain(|path| seen.insert(path.clone()));
+
+    for path in &overrides {
+        let name = Path::new(OVERRIDES).join(path);
+        let name = name
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, options)?;
+        io::copy(&mut fs::File::open(instance_dir.join(path))?, &mut zip)?;
+    }
+
+    zip.finish()?.sync_all()?;
+    fs::rename(&part, output)?;
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::ids::FileId;
+    use crate::modpack::read_manifest;
+
+    #[test]
+    fn writes_pack_with_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let instance = dir.path().join("instance");
+        fs::create_dir_all(instance.join("config/sub")).unwrap();
+        fs::create_dir_all(instance.join("mods")).unwrap();
+        fs::write(instance.join("config/sub/a.toml"), "a = 1").unwrap();
+        fs::write(instance.join("options.txt"), "fov:70").unwrap();
+        fs::write(instance.join("mods/local.jar"), "jar").unwrap();
+
+        let options = ExportOptions {
+            name: "Pack".to_string(),
+            minecraft_version: "1.20.1".to_string(),
+            ..Default::default()
+        };
+        let manifest = manifest(
+            &options,
+            vec![ManifestFile {
+                project_id: ModId(238222),
+                file_id: FileId(4712866),
+                required: true,
+            }],
+        );
+        let output = dir.path().join("pack.zip");
+        let mut overrides = write_pack(
+            &output,
+            &instance,
+            &manifest,
+            "<ul>\n</ul>\n",
+            &[PathBuf::from("mods/local.jar")],
+            &options.overrides,
+        )
+        .unwrap();
+        overrides.sort();
+        assert_eq!(
+            overrides,
+            [
+                PathBuf::from("config/sub/a.toml"),
+                PathBuf::from("mods/local.jar"),
+                PathBuf::from("options.txt")
+            ]
+        );
+        assert!(!part_path(&output).exists());
+
+        let read = read_manifest(fs::File::open(&output).unwrap()).unwrap();
+        assert_eq!(read.files, manifest.files);
+        let mut archive = zip::ZipArchive::new(fs::File::open(&output).unwrap()).unwrap();
+        assert!(archive.by_name("overrides/config/sub/a.toml").is_ok());
+        assert!(archive.by_name(MODLIST_FILE_NAME).is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("pack.zip");
+        let cf = CurseForge::new("key").unwrap();
+        let exported = cf
+            .export_modpack(dir.path(), &ExportOptions::default(), &output)
+            .await;
+        assert!(matches!(exported, Err(Error::InvalidModpack(_))));
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn escapes_modlist() {
+        assert_eq!(escape(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
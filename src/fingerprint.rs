@@ -0,0 +1,232 @@
+//! CurseForge file fingerprints.
+//!
+//! A fingerprint is the 32-bit MurmurHash2 (seed `1`) of a file's content once every tab,
+//! line feed, carriage return and space byte has been removed. It is the value found in
+//! [`File::file_fingerprint`](crate::definitions::File::file_fingerprint) and expected by the
+//! fingerprint endpoints.
+
+use std::fs;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+const SEED: u32 = 1;
+const M: u32 = 0x5bd1_e995;
+const R: u32 = 24;
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Whether `byte` is skipped when fingerprinting.
+pub fn is_ignored_byte(byte: u8) -> bool {
+    matches!(byte, 9 | 10 | 13 | 32)
+}
+
+/// Number of bytes of `data` that are hashed, i.e. excluding whitespace.
+pub fn normalized_len(data: &[u8]) -> u32 {
+    data.iter().filter(|&&byte| !is_ignored_byte(byte)).count() as u32
+}
+
+pub fn compute_fingerprint(data: &[u8]) -> u32 {
+    let mut fingerprinter = Fingerprinter::new(normalized_len(data));
+    fingerprinter.update(data);
+    fingerprinter.finish()
+}
+
+/// Fingerprints a reader in two passes, the first one measures the normalized length.
+pub fn fingerprint_reader<R: Read + Seek>(reader: &mut R) -> io::Result<u32> {
+    let start = reader.stream_position()?;
+    let mut buffer = vec![0; BUFFER_SIZE];
+
+    let mut len = 0u32;
+    for_each_chunk(reader, &mut buffer, |chunk| {
+        len = len.wrapping_add(normalized_len(chunk));
+    })?;
+
+    reader.seek(SeekFrom::Start(start))?;
+    let mut fingerprinter = Fingerprinter::new(len);
+    for_each_chunk(reader, &mut buffer, |chunk| fingerprinter.update(chunk))?;
+    Ok(fingerprinter.finish())
+}
+
+pub fn fingerprint_file(path: impl AsRef<Path>) -> io::Result<u32> {
+    fingerprint_reader(&mut BufReader::new(fs::File::open(path)?))
+}
+
+fn for_each_chunk<R: Read>(
+    reader: &mut R,
+    buffer: &mut [u8],
+    mut f: impl FnMut(&[u8]),
+) -> io::Result<()> {
+    loop {
+        match reader.read(buffer) {
+            Ok(0) => return Ok(()),
+            Ok(read) => f(&buffer[..read]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Incremental fingerprint computation.
+///
+/// MurmurHash2 mixes the input length into its initial state, so the normalized length of the
+/// whole content (see [`normalized_len`]) must be known before feeding it with
+/// [`Fingerprinter::update`].
+#[derive(Debug, Clone)]
+pub struct Fingerprinter {
+    hash: u32,
+    block: [u8; 4],
+    block_len: usize,
+}
+
+impl Fingerprinter {
+    pub fn new(normalized_len: u32) -> Self {
+        Self {
+            hash: SEED ^ normalized_len,
+            block: [0; 4],
+            block_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data.iter().filter(|&&byte| !is_ignored_byte(byte)) {
+            self.block[self.block_len] = byte;
+            self.block_len += 1;
+            if self.block_len == 4 {
+                self.mix_block();
+            }
+        }
+    }
+
+    pub fn finish(mut self) -> u32 {
+        let tail = &self.block[..self.block_len];
+        if tail.len() >= 3 {
+            self.hash ^= u32::from(tail[2]) << 16;
+        }
+        if tail.len() >= 2 {
+            self.hash ^= u32::from(tail[1]) << 8;
+        }
+        if let Some(&first) = tail.first() {
+            self.hash ^= u32::from(first);
+            self.hash = self.hash.wrapping_mul(M);
+        }
+
+        let mut hash = self.hash;
+        hash ^= hash >> 13;
+        hash = hash.wrapping_mul(M);
+        hash ^= hash >> 15;
+        hash
+    }
+
+    fn mix_block(&mut self) {
+        let mut k = u32::from_le_bytes(self.block);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        self.hash = self.hash.wrapping_mul(M);
+        self.hash ^= k;
+        self.block_len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn known_fingerprints() {
+        assert_eq!(compute_fingerprint(b""), 1540447798);
+        assert_eq!(compute_fingerprint(b"a"), 626045324);
+        assert_eq!(compute_fingerprint(b"ab"), 1692487918);
+        assert_eq!(compute_fingerprint(b"abc"), 1621425345);
+        assert_eq!(compute_fingerprint(b"abcd"), 3376380438);
+        assert_eq!(compute_fingerprint(b"Hello, World!"), 1961219979);
+        assert_eq!(
+            compute_fingerprint(b"The quick brown fox jumps over the lazy dog"),
+            3751777527
+        );
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(compute_fingerprint(&bytes), 2094645347);
+    }
+
+    /// Plain MurmurHash2, without skipping whitespace.
+    fn murmur2(data: &[u8], seed: u32) -> u32 {
+        let mut fingerprinter = Fingerprinter {
+            hash: seed ^ data.len() as u32,
+            block: [0; 4],
+            block_len: 0,
+        };
+        for &byte in data {
+            fingerprinter.block[fingerprinter.block_len] = byte;
+            fingerprinter.block_len += 1;
+            if fingerprinter.block_len == 4 {
+                fingerprinter.mix_block();
+            }
+        }
+        fingerprinter.finish()
+    }
+
+    /// The verification value of MurmurHash2 published with SMHasher, the hash of the hashes of
+    /// the keys `[]`, `[0]`, `[0, 1]`, ... `[0, ..., 254]` with seeds 256 down to 1.
+    #[test]
+    fn smhasher_verification() {
+        let key: Vec<u8> = (0..=255).collect();
+        let hashes: Vec<u8> = (0..256)
+            .flat_map(|i| murmur2(&key[..i], 256 - i as u32).to_le_bytes())
+            .collect();
+        assert_eq!(murmur2(&hashes, 0), 0x27864C1E);
+    }
+
+    #[test]
+    fn whitespace_is_ignored() {
+        assert_eq!(
+            compute_fingerprint(b" a\tb\r\nc d "),
+            compute_fingerprint(b"abcd")
+        );
+        assert_ne!(compute_fingerprint(b"a\x0bb"), compute_fingerprint(b"ab"));
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let expected = compute_fingerprint(&data);
+
+        let mut fingerprinter = Fingerprinter::new(normalized_len(&data));
+        for chunk in data.chunks(7) {
+            fingerprinter.update(chunk);
+        }
+        assert_eq!(fingerprinter.finish(), expected);
+
+        assert_eq!(
+            fingerprint_reader(&mut Cursor::new(&data)).unwrap(),
+            expected
+        );
+    }
+
+    /// Compares the fingerprints of real files with the `fileFingerprint` returned by the API.
+    #[tokio::test]
+    #[ignore = "needs CURSEFORGE_API_KEY and network access"]
+    async fn api_fingerprints() -> anyhow::Result<()> {
+        dotenvy::dotenv().ok();
+        let cf = crate::CurseForge::new(&std::env::var("CURSEFORGE_API_KEY")?)?;
+
+        // Just Enough Items, its files are small jars.
+        let project = cf.get_mod(crate::ids::ModId(238222)).await?.data;
+        let files: Vec<_> = (project.latest_files.iter())
+            .filter_map(|file| Some((file, file.download_url.as_deref()?)))
+            .take(3)
+            .collect();
+        assert!(!files.is_empty());
+        for (file, url) in files {
+            let body = reqwest::get(url).await?.error_for_status()?.bytes().await?;
+            assert_eq!(
+                i64::from(compute_fingerprint(&body)),
+                file.file_fingerprint,
+                "file {}",
+                file.id
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! API objects and a local HTTP server shared by the unit tests.
+
+use crate::definitions::{File, Mod};
+use reqwest::StatusCode;
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+/// A URL nothing can listen on, requests to it fail right away with a transport error.
+pub(crate) const UNREACHABLE_URL: &str = "http://127.0.0.1:0";
+
+/// A file as returned by the API, `fields` replacing the default ones.
+pub(crate) fn file(fields: Value) -> File {
+    with_fields(
+        json!({
+            "id": 4571007,
+            "gameId": 432,
+            "modId": 238222,
+            "isAvailable": true,
+            "fileName": "mod.jar",
+            "releaseType": 1,
+            "fileStatus": 4,
+            "hashes": [],
+            "fileDate": "2024-01-01T00:00:00Z",
+            "fileLength": 0,
+            "downloadCount": 0,
+            "gameVersions": [],
+            "sortableGameVersions": [],
+            "dependencies": [],
+            "fileFingerprint": 0,
+            "modules": [],
+        }),
+        fields,
+    )
+}
+
+/// A mod as returned by the API, `fields` replacing the default ones.
+pub(crate) fn project(fields: Value) -> Mod {
+    with_fields(
+        json!({
+            "id": 238222,
+            "gameId": 432,
+            "name": "Mod",
+            "slug": "mod",
+            "links": {},
+            "status": 4,
+            "downloadCount": 0,
+            "isFeatured": false,
+            "primaryCategoryId": 0,
+            "categories": [],
+            "authors": [],
+            "screenshots": [],
+            "mainFileId": 0,
+            "latestFiles": [],
+            "latestFilesIndexes": [],
+            "latestEarlyAccessFilesIndexes": [],
+            "dateCreated": "2024-01-01T00:00:00Z",
+            "dateModified": "2024-01-01T00:00:00Z",
+            "dateReleased": "2024-01-01T00:00:00Z",
+            "gamePopularityRank": 0,
+            "isAvailable": true,
+            "thumbsUpCount": 0,
+        }),
+        fields,
+    )
+}
+
+fn with_fields<T: serde::de::DeserializeOwned>(mut defaults: Value, fields: Value) -> T {
+    let Value::Object(fields) = fields else {
+        panic!("fixture fields must be an object");
+    };
+    defaults.as_object_mut().unwrap().extend(fields);
+    serde_json::from_value(defaults).unwrap()
+}
+
+/// A request received by a [`TestServer`].
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub(crate) path: String,
+    /// Header names are lowercase.
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    status: StatusCode,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub(crate) fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+/// An HTTP server on a local port answering every request with `respond`, one connection per
+/// request. It runs on its own thread so it works on any runtime.
+pub(crate) struct TestServer {
+    pub(crate) url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    pub(crate) fn start(respond: impl Fn(&Request) -> Response + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let request = read_request(&mut BufReader::new(&mut stream));
+                received.lock().unwrap().push(request.clone());
+
+                let response = respond(&request);
+                let mut head = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in &response.headers {
+                    head.push_str(&format!("{name}: {value}\r\n"));
+                }
+                head.push_str("\r\n");
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(&response.body).unwrap();
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// Every request received so far.
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// Reads the request line and headers, request bodies are read and ignored.
+fn read_request(reader: &mut impl BufRead) -> Request {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let path = line.split(' ').nth(1).unwrap_or_default().to_string();
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).unwrap();
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        headers.push((name.to_ascii_lowercase(), value.trim().to_string()));
+    }
+    let request = Request { path, headers };
+    let length = request
+        .header("content-length")
+        .map_or(0, |len| len.parse().unwrap());
+    reader.read_exact(&mut vec![0; length]).unwrap();
+    request
+}
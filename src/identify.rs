@@ -0,0 +1,222 @@
+use crate::CurseForge;
+use crate::definitions::{
+    FingerprintMatch, FingerprintsMatchesResult, GetFingerprintMatchesRequestBody,
+};
+use crate::error::Result;
+use crate::fingerprint::fingerprint_file;
+use crate::ids::GameId;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Number of fingerprints sent in a single fingerprint matches request.
+const FINGERPRINTS_PER_REQUEST: usize = 500;
+
+#[derive(Debug, Clone)]
+pub struct IdentifiedFile {
+    pub path: PathBuf,
+    pub fingerprint: u32,
+    /// The CurseForge mod and file this local file is an exact copy of, if any.
+    pub matched: Option<FingerprintMatch>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DirectoryReport {
+    /// Every file found in the directory, sorted by path.
+    pub files: Vec<IdentifiedFile>,
+}
+
+impl DirectoryReport {
+    pub fn matched(&self) -> impl Iterator<Item = (&Path, &FingerprintMatch)> {
+        self.files
+            .iter()
+            .filter_map(|file| Some((file.path.as_path(), file.matched.as_ref()?)))
+    }
+
+    pub fn unmatched(&self) -> impl Iterator<Item = &Path> {
+        self.files
+            .iter()
+            .filter(|file| file.matched.is_none())
+            .map(|file| file.path.as_path())
+    }
+}
+
+impl CurseForge {
+    /// Fingerprints every file under `path` and looks them up among the files of `game_id`.
+    pub async fn identify_directory(
+        &self,
+        path: impl AsRef<Path>,
+        game_id: GameId,
+    ) -> Result<DirectoryReport> {
+        let root = path.as_ref().to_path_buf();
+        let fingerprints = crate::blocking(move || fingerprint_directory(&root)).await?;
+
+        let matches = self
+            .match_fingerprints(Some(game_id), fingerprints.iter().map(|(_, f)| *f))
+            .await?;
+
+        let files = fingerprints
+            .into_iter()
+            .map(|(path, fingerprint)| IdentifiedFile {
+                matched: matches.get(&i64::from(fingerprint)).cloned(),
+                path,
+                fingerprint,
+            })
+            .collect();
+        Ok(DirectoryReport { files })
+    }
+}
+
+impl CurseForge {
+    /// Looks up fingerprints in batches, returning the exact matches keyed by fingerprint.
+    pub(crate) async fn match_fingerprints(
+        &self,
+        game_id: Option<GameId>,
+        fingerprints: impl IntoIterator<Item = u32>,
+    ) -> Result<HashMap<i64, FingerprintMatch>> {
+        let mut results = Vec::new();
+        for body in request_bodies(fingerprints) {
+            let result = match game_id {
+                Some(game_id) => {
+                    self.get_fingerprints_matches_by_game_id(game_id, &body)
+                        .await?
+                }
+                None => self.get_fingerprints_matches(&body).await?,
+            };
+            results.push(result.data);
+        }
+        Ok(merge_matches(results))
+    }
+}
+
+/// Splits the distinct fingerprints into requests small enough for the API.
+fn request_bodies(
+    fingerprints: impl IntoIterator<Item = u32>,
+) -> Vec<GetFingerprintMatchesRequestBody> {
+    let mut unique = HashSet::new();
+    let requested: Vec<i64> = fingerprints
+        .into_iter()
+        .map(i64::from)
+        .filter(|fingerprint| unique.insert(*fingerprint))
+        .collect();
+    requested
+        .chunks(FINGERPRINTS_PER_REQUEST)
+        .map(|chunk| GetFingerprintMatchesRequestBody {
+            fingerprints: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// Collects the exact matches of every result by fingerprint, except the ones also reported as
+/// unmatched.
+fn merge_matches(
+    results: impl IntoIterator<Item = FingerprintsMatchesResult>,
+) -> HashMap<i64, FingerprintMatch> {
+    let mut matches = HashMap::new();
+    for result in results {
+        let unmatched: HashSet<i64> = result.unmatched_fingerprints.into_iter().collect();
+        for exact in result.exact_matches {
+            if !unmatched.contains(&exact.file.file_fingerprint) {
+                matches.insert(exact.file.file_fingerprint, exact);
+            }
+        }
+    }
+    matches
+}
+
+/// Fingerprints every regular file under `root`, recursively, sorted by path.
+pub(crate) fn fingerprint_directory(root: &Path) -> io::Result<Vec<(PathBuf, u32)>> {
+    list_files(root)?
+        .into_iter()
+        .map(|path| {
+            let fingerprint = fingerprint_file(&path)?;
+            Ok((path, fingerprint))
+        })
+        .collect()
+}
+
+/// Lists every regular file under `root`, recursively, sorted by path.
+pub(crate) fn list_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                paths.push(entry.path());
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::compute_fingerprint;
+    use crate::fixtures;
+    use crate::ids::ModId;
+    use serde_json::json;
+
+    fn result(exact: &[i64], unmatched: &[i64]) -> FingerprintsMatchesResult {
+        let exact_matches = exact
+            .iter()
+            .map(|fingerprint| FingerprintMatch {
+                id: ModId(238222),
+                file: fixtures::file(json!({ "id": fingerprint, "fileFingerprint": fingerprint })),
+                latest_files: Vec::new(),
+            })
+            .collect();
+        FingerprintsMatchesResult {
+            is_cache_built: true,
+            exact_matches,
+            exact_fingerprints: exact.to_vec(),
+            partial_matches: Vec::new(),
+            partial_match_fingerprints: HashMap::new(),
+            installed_fingerprints: Vec::new(),
+            unmatched_fingerprints: unmatched.to_vec(),
+        }
+    }
+
+    #[test]
+    fn batches_distinct_fingerprints() {
+        let bodies = request_bodies((0..1200).chain(0..10));
+        let sizes: Vec<usize> = bodies.iter().map(|body| body.fingerprints.len()).collect();
+        assert_eq!(sizes, [500, 500, 200]);
+        assert_eq!(bodies[2].fingerprints.last(), Some(&1199));
+    }
+
+    #[test]
+    fn merges_exact_matches() {
+        let matches = merge_matches([result(&[1, 2], &[3]), result(&[4, 5], &[5, 6])]);
+        let mut fingerprints: Vec<i64> = matches.keys().copied().collect();
+        fingerprints.sort_unstable();
+        assert_eq!(fingerprints, [1, 2, 4]);
+        assert_eq!(matches[&4].file.id.0, 4);
+    }
+
+    #[test]
+    fn fingerprints_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("config/sub")).unwrap();
+        std::fs::write(dir.path().join("b.jar"), b"second").unwrap();
+        std::fs::write(dir.path().join("a.jar"), b"first").unwrap();
+        std::fs::write(dir.path().join("config/sub/c.toml"), b"third").unwrap();
+
+        let fingerprints = fingerprint_directory(dir.path()).unwrap();
+        let expected = [
+            (dir.path().join("a.jar"), compute_fingerprint(b"first")),
+            (dir.path().join("b.jar"), compute_fingerprint(b"second")),
+            (
+                dir.path().join("config/sub/c.toml"),
+                compute_fingerprint(b"third"),
+            ),
+        ];
+        assert_eq!(fingerprints, expected);
+    }
+}
@@ -0,0 +1,79 @@
+//! Typed ids, so that for instance a file id can't be passed where a mod id is expected.
+//!
+//! Every id serializes as the bare integer the API uses.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+macro_rules! id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub i32);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl From<i32> for $name {
+            fn from(id: i32) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for i32 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+    };
+}
+
+id!(GameId);
+id!(ModId);
+id!(FileId);
+id!(CategoryId);
+id!(
+    /// Id of a class, the top level categories of a game.
+    ClassId
+);
+id!(AuthorId);
+id!(GameVersionTypeId);
+
+impl GameId {
+    pub const WORLD_OF_WARCRAFT: Self = Self(1);
+    pub const MINECRAFT: Self = Self(432);
+}
+
+impl ClassId {
+    pub const MINECRAFT_BUKKIT_PLUGINS: Self = Self(5);
+    pub const MINECRAFT_MODS: Self = Self(6);
+    pub const MINECRAFT_RESOURCE_PACKS: Self = Self(12);
+    pub const MINECRAFT_WORLDS: Self = Self(17);
+    pub const MINECRAFT_MODPACKS: Self = Self(4471);
+    pub const MINECRAFT_CUSTOMIZATION: Self = Self(4546);
+    pub const MINECRAFT_ADDONS: Self = Self(4559);
+    pub const MINECRAFT_SHADERS: Self = Self(6552);
+    pub const MINECRAFT_DATA_PACKS: Self = Self(6945);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_integers() {
+        assert_eq!(serde_json::to_string(&ModId(238222)).unwrap(), "238222");
+        assert_eq!(
+            serde_json::from_str::<FileId>("4712866").unwrap(),
+            FileId(4712866)
+        );
+        assert_eq!(GameId::MINECRAFT.to_string(), "432");
+    }
+}
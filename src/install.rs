@@ -0,0 +1,311 @@
+use crate::CurseForge;
+use crate::definitions::{File, Mod};
+use crate::download::{BulkDownload, BulkDownloadOptions, DownloadLocation};
+use crate::error::{Error, Result};
+use crate::ids::{ClassId, FileId, ModId};
+use crate::modpack::{ManifestFile, Modpack, ResolvedFile};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Minecraft class ids and the instance folder their files are installed to.
+const CLASS_FOLDERS: [(ClassId, &str); 4] = [
+    (ClassId::MINECRAFT_MODS, "mods"),
+    (ClassId::MINECRAFT_RESOURCE_PACKS, "resourcepacks"),
+    (ClassId::MINECRAFT_WORLDS, "saves"),
+    (ClassId::MINECRAFT_SHADERS, "shaderpacks"),
+];
+
+#[derive(Debug, Clone)]
+pub struct InstalledFile {
+    pub mod_id: ModId,
+    pub file_id: FileId,
+    pub path: PathBuf,
+}
+
+/// A file that can't be downloaded automatically, it has to be downloaded by the user.
+#[derive(Debug, Clone)]
+pub struct ManualDownload {
+    pub mod_id: ModId,
+    pub file_id: FileId,
+    pub file_name: Option<String>,
+    pub website_url: Option<String>,
+    /// Where the file is expected once downloaded.
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default)]
+pub struct InstallReport {
+    pub installed: Vec<InstalledFile>,
+    pub manual: Vec<ManualDownload>,
+    /// Downloads that failed, the others were still installed.
+    pub failed: Vec<BulkDownload>,
+    /// Manifest entries the API returned no file or mod for.
+    pub missing: Vec<ManifestFile>,
+    /// Files extracted from the overrides folder, relative to the target directory.
+    pub overrides: Vec<PathBuf>,
+}
+
+/// Instance folder the files of a class are installed to, `mods` for unknown classes.
+pub fn install_folder(class_id: Option<ClassId>) -> &'static str {
+    CLASS_FOLDERS
+        .iter()
+        .find(|(id, _)| Some(*id) == class_id)
+        .map_or("mods", |(_, folder)| folder)
+}
+
+/// Path a file of `project` is installed to, relative to the instance folder.
+pub(crate) fn install_path(file: &File, project: &Mod) -> PathBuf {
+    let file_name = file
+        .file_name
+        .as_deref()
+        .and_then(|name| Path::new(name).file_name())
+        .map_or_else(
+            || format!("{}.jar", file.id).into(),
+            |name| name.to_os_string(),
+        );
+    Path::new(install_folder(project.class_id)).join(file_name)
+}
+
+impl CurseForge {
+    /// Installs a modpack into `target_dir`.
+    ///
+    /// Every file of the manifest is downloaded to the folder matching its class, then the
+    /// overrides folder of the pack is extracted on top. Files that can't be downloaded
+    /// automatically, see [`DownloadLocation::of`], are reported in [`InstallReport::manual`].
+    pub async fn install_modpack(
+        &self,
+        pack: &Modpack,
+        target_dir: impl AsRef<Path>,
+    ) -> Result<InstallReport> {
+        let target_dir = target_dir.as_ref();
+        let resolved = self.resolve_modpack(&pack.manifest).await?;
+        let mut report = InstallReport {
+            missing: resolved.missing,
+            ..Default::default()
+        };
+
+        let mut downloads = Vec::new();
+        for ResolvedFile { file, project, .. } in resolved.files {
+            let path = target_dir.join(install_path(&file, &project));
+
+            match DownloadLocation::of(&file, &project) {
+                DownloadLocation::Manual {
+                    mod_id,
+                    file_id,
+                    website_url,
+                } => report.manual.push(ManualDownload {
+                    mod_id,
+                    file_id,
+                    file_name: file.file_name,
+                    website_url,
+                    path,
+                }),
+                DownloadLocation::Api(_) | DownloadLocation::Cdn(_) => {
+                    downloads.push((file, path));
+                }
+            }
+        }
+
+        let outcomes = self
+            .download_files(downloads, &BulkDownloadOptions::default())
+            .await;
+        for download in outcomes {
+            if download.outcome.is_success() {
+                report.installed.push(InstalledFile {
+                    mod_id: download.mod_id,
+                    file_id: download.file_id,
+                    path: download.dest,
+                });
+            } else {
+                report.failed.push(download);
+            }
+        }
+
+        let archive = pack.path.clone();
+        let overrides = pack.manifest.overrides.clone();
+        let target = target_dir.to_path_buf();
+        report.overrides =
+            crate::blocking(move || extract_overrides(&archive, &overrides, &target)).await?;
+
+        Ok(report)
+    }
+}
+
+/// Extracts the entries under the `overrides` folder of a pack into `target_dir`.
+fn extract_overrides(archive: &Path, overrides: &str, target_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut archive = ZipArchive::new(fs::File::open(archive)?)?;
+    let overrides = Path::new(overrides);
+    let mut extracted = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let Some(name) = entry.enclosed_name() else {
+            return Err(Error::InvalidModpack(format!(
+                "unsafe path `{}` in archive",
+                entry.name()
+            )));
+        };
+        let Ok(relative) = name.strip_prefix(overrides) else {
+            continue;
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let dest = target_dir.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&dest)?;
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut fs::File::create(&dest)?)?;
+        extracted.push(relative.to_path_buf());
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::compute_fingerprint;
+    use crate::fixtures::{self, Response, TestServer};
+    use serde_json::json;
+    use std::io::Write;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const JAR: &[u8] = b"jar content";
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut writer = ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    /// Serves the files and mods of a pack of three entries: a downloadable mod, a resource pack
+    /// whose project disallows distribution and a file the API doesn't know.
+    fn serve_pack() -> TestServer {
+        TestServer::start(|request| {
+            let url = format!("http://{}/a.jar", request.header("host").unwrap());
+            let body = match request.path.as_str() {
+                "/mods/files" => json!({"data": [
+                    fixtures::file(json!({
+                        "id": 10,
+                        "modId": 1,
+                        "fileName": "a.jar",
+                        "downloadUrl": url,
+                        "fileLength": JAR.len(),
+                        "fileFingerprint": compute_fingerprint(JAR),
+                    })),
+                    fixtures::file(json!({"id": 20, "modId": 2, "fileName": "b.zip"})),
+                ]}),
+                "/mods" => json!({"data": [
+                    fixtures::project(json!({"id": 1, "classId": 6})),
+                    fixtures::project(json!({
+                        "id": 2,
+                        "classId": 12,
+                        "allowModDistribution": false,
+                        "links": {"websiteUrl": "https://example.com/b"},
+                    })),
+                ]}),
+                "/a.jar" => return Response::new(200, JAR),
+                _ => return Response::new(404, ""),
+            };
+            Response::new(200, body.to_string())
+        })
+    }
+
+    #[tokio::test]
+    async fn installs_modpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pack.zip");
+        let manifest = json!({
+            "minecraft": {"version": "1.20.1", "modLoaders": []},
+            "manifestType": "minecraftModpack",
+            "manifestVersion": 1,
+            "name": "Pack",
+            "version": "1.0.0",
+            "author": "someone",
+            "files": [
+                {"projectID": 1, "fileID": 10},
+                {"projectID": 2, "fileID": 20},
+                {"projectID": 3, "fileID": 30},
+            ],
+            "overrides": "overrides",
+        });
+        write_zip(
+            &archive,
+            &[
+                ("manifest.json", &manifest.to_string()),
+                ("overrides/options.txt", "fov:70"),
+            ],
+        );
+        let pack = Modpack::open(&archive).unwrap();
+        let server = serve_pack();
+        let cf = CurseForge::builder()
+            .api_key("key")
+            .base_url(&server.url)
+            .build()
+            .unwrap();
+
+        let target = dir.path().join("instance");
+        let report = cf.install_modpack(&pack, &target).await.unwrap();
+
+        assert!(report.failed.is_empty(), "{:?}", report.failed);
+        let installed: Vec<_> = report
+            .installed
+            .iter()
+            .map(|f| (f.file_id, &f.path))
+            .collect();
+        assert_eq!(installed, [(FileId(10), &target.join("mods/a.jar"))]);
+        assert_eq!(fs::read(target.join("mods/a.jar")).unwrap(), JAR);
+
+        let [manual] = report.manual.as_slice() else {
+            panic!("{:?}", report.manual);
+        };
+        assert_eq!((manual.mod_id, manual.file_id), (ModId(2), FileId(20)));
+        assert_eq!(manual.website_url.as_deref(), Some("https://example.com/b"));
+        assert_eq!(manual.path, target.join("resourcepacks/b.zip"));
+        assert!(!manual.path.exists());
+
+        let missing: Vec<_> = report.missing.iter().map(|f| f.file_id).collect();
+        assert_eq!(missing, [FileId(30)]);
+        assert_eq!(report.overrides, [PathBuf::from("options.txt")]);
+    }
+
+    #[test]
+    fn extracts_only_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("pack.zip");
+        write_zip(
+            &archive,
+            &[
+                ("manifest.json", "{}"),
+                ("overrides/config/a.toml", "a = 1"),
+                ("overrides/options.txt", "fov:70"),
+            ],
+        );
+
+        let target = dir.path().join("instance");
+        let mut extracted = extract_overrides(&archive, "overrides", &target).unwrap();
+        extracted.sort();
+        assert_eq!(
+            extracted,
+            [PathBuf::from("config/a.toml"), PathBuf::from("options.txt")]
+        );
+        assert_eq!(
+            fs::read_to_string(target.join("options.txt")).unwrap(),
+            "fov:70"
+        );
+        assert!(!target.join("manifest.json").exists());
+    }
+}
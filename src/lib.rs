@@ -1,35 +1,135 @@
-use anyhow::Result;
-use reqwest::header::{HeaderMap, HeaderValue};
+use builder::Connection;
+use cache::{CacheStats, Caches, Endpoint};
+use disk_cache::DiskCache;
+use rate_limit::RateLimiter;
+use reqwest::header::HeaderValue;
+use std::sync::Arc;
+use std::time::Duration;
 
+pub mod addons;
+pub mod batch;
+pub mod builder;
+pub mod cache;
 pub mod definitions;
+pub mod disk_cache;
+pub mod download;
+pub mod error;
+pub mod export;
+pub mod fingerprint;
+#[cfg(test)]
+mod fixtures;
+pub mod identify;
+pub mod ids;
+pub mod install;
+pub mod loader;
+pub mod lockfile;
+pub mod modpack;
+pub mod pagination;
+pub mod rate_limit;
 pub mod requests;
+pub mod resolve;
+pub mod retry;
+pub mod search;
+pub mod updates;
+
+pub use builder::CurseForgeBuilder;
+pub use cache::{Cache, CacheConfig};
+pub use error::{Error, Result};
+pub use pagination::StreamOptions;
+pub use rate_limit::RateLimit;
+pub use retry::RetryPolicy;
 
+/// A CurseForge API client.
+///
+/// The client is cheap to clone: clones share the same connection pool, rate limiter and
+/// configuration, so a single instance can be created at startup and handed to every task.
+/// It is `Send + Sync` and all its methods take `&self`, it can be used concurrently without
+/// any extra synchronization. Use [`CurseForge::to_builder`] to derive a client with different
+/// settings that still shares the connection pool.
+#[derive(Debug, Clone)]
 pub struct CurseForge {
     client: reqwest::Client,
+    config: Arc<ClientConfig>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    caches: Caches,
+}
+
+#[derive(Debug)]
+pub(crate) struct ClientConfig {
+    api_key: HeaderValue,
+    user_agent: Option<HeaderValue>,
+    timeout: Option<Duration>,
+    /// Settings the client was built with, `None` if it was supplied.
+    connection: Option<Connection>,
+    retry_policy: RetryPolicy,
+    base_url: String,
+    base_url_v2: String,
 }
 
 impl CurseForge {
     pub fn new(api_key: &str) -> Result<Self> {
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "x-api-key",
-            HeaderValue::from_str(api_key)
-                .map_err(|_| anyhow::anyhow!("Invalid CURSE_FORGE_API_KEY value"))?,
-        );
+        Self::builder().api_key(api_key).build()
+    }
 
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()
-            .map_err(|_| anyhow::anyhow!("Unable to create CurseForge client"))?;
+    pub fn builder() -> CurseForgeBuilder {
+        CurseForgeBuilder::new()
+    }
+
+    /// Returns a builder preloaded with the settings of this client.
+    ///
+    /// The derived client reuses this client's connection pool and rate limiter unless they are
+    /// replaced on the builder, changing a connection-level setting creates a new pool.
+    pub fn to_builder(&self) -> CurseForgeBuilder {
+        CurseForgeBuilder::from_client(self)
+    }
+
+    /// Hit and miss counters of the in-memory cache, `None` if it is disabled.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.caches.memory.as_ref().map(|cache| cache.stats())
+    }
 
-        Ok(Self { client })
+    /// The on-disk response cache, to prune it.
+    pub fn disk_cache(&self) -> Option<&DiskCache> {
+        self.caches.disk.as_deref()
     }
+
+    /// Drops every cached response.
+    pub async fn invalidate_cache(&self) {
+        for cache in self.caches.layers() {
+            cache.invalidate(None).await;
+        }
+    }
+
+    /// Drops the cached responses of an endpoint.
+    pub async fn invalidate_endpoint(&self, endpoint: Endpoint) {
+        for cache in self.caches.layers() {
+            cache.invalidate(Some(endpoint)).await;
+        }
+    }
+}
+
+/// Runs blocking filesystem work off the async runtime, propagating panics.
+pub(crate) async fn blocking<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
 }
 
+const _: () = {
+    const fn assert_shareable<T: Send + Sync + Clone + 'static>() {}
+    assert_shareable::<CurseForge>();
+};
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::definitions::SearchModsParams;
+    use crate::ids::GameId;
+    use anyhow::Result;
 
     #[tokio::test]
     async fn manual_testing() -> Result<()> {
@@ -41,7 +141,7 @@ mod tests {
         // Search for Minecraft mods
         let mods = cf
             .search_mods(&SearchModsParams {
-                game_id: 432, // Minecraft
+                game_id: GameId::MINECRAFT,
                 search_filter: Some("Complementary Shaders".to_string()),
                 sort_field: Some(definitions::ModsSearchSortField::TotalDownloads),
                 sort_order: Some(definitions::SortOrder::Desc),
@@ -0,0 +1,286 @@
+use crate::CurseForge;
+use crate::batch::Batch;
+use crate::definitions::{File, Mod};
+use crate::error::{Error, Result};
+use crate::ids::{FileId, ModId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+const DEFAULT_WINDOW: Duration = Duration::from_millis(10);
+
+/// Coalesces lookups of single mods and files into batched requests.
+///
+/// Lookups made within `window` of the first pending one are sent together through
+/// `get_mods`/`get_files`, each id once, and every caller gets its own copy of the result.
+/// Clones share the pending lookups, so a single loader can be handed to every code path.
+#[derive(Debug, Clone)]
+pub struct DataLoader {
+    cf: CurseForge,
+    mods: Arc<Queue<ModId>>,
+    files: Arc<Queue<FileId>>,
+}
+
+impl DataLoader {
+    pub fn new(cf: CurseForge) -> Self {
+        Self::with_window(cf, DEFAULT_WINDOW)
+    }
+
+    /// A loader waiting `window` for more lookups before sending a batch.
+    pub fn with_window(cf: CurseForge, window: Duration) -> Self {
+        Self {
+            cf,
+            mods: Arc::new(Queue::new(window)),
+            files: Arc::new(Queue::new(window)),
+        }
+    }
+
+    /// Same as [`CurseForge::get_mod`], batched with the other pending lookups.
+    ///
+    /// A failure of the batched request is returned to every caller as [`Error::Shared`], use
+    /// [`Error::root`] to match on the underlying error.
+    pub async fn load_mod(&self, mod_id: ModId) -> Result<Mod> {
+        self.mods.load(&self.cf, mod_id).await
+    }
+
+    /// Same as [`CurseForge::get_mod_file`] without the mod id, batched with the other pending
+    /// lookups. Failures are wrapped as in [`DataLoader::load_mod`].
+    pub async fn load_file(&self, file_id: FileId) -> Result<File> {
+        self.files.load(&self.cf, file_id).await
+    }
+}
+
+/// Ids that can be looked up in batches.
+trait Key: Copy + Eq + Hash + Send + Sync + 'static {
+    type Value: Clone + Send + 'static;
+
+    fn fetch(
+        cf: &CurseForge,
+        ids: Vec<Self>,
+    ) -> impl Future<Output = Result<Batch<Self, Self::Value>>> + Send;
+
+    fn id(value: &Self::Value) -> Self;
+
+    fn not_found(cf: &CurseForge, id: Self) -> Error;
+}
+
+impl Key for ModId {
+    type Value = Mod;
+
+    async fn fetch(cf: &CurseForge, ids: Vec<Self>) -> Result<Batch<Self, Mod>> {
+        cf.get_mods_all(&ids).await
+    }
+
+    fn id(value: &Mod) -> Self {
+        value.id
+    }
+
+    fn not_found(cf: &CurseForge, id: Self) -> Error {
+        Error::NotFound {
+            url: format!("{}/mods/{id}", cf.config.base_url),
+        }
+    }
+}
+
+impl Key for FileId {
+    type Value = File;
+
+    async fn fetch(cf: &CurseForge, ids: Vec<Self>) -> Result<Batch<Self, File>> {
+        cf.get_files_all(&ids).await
+    }
+
+    fn id(value: &File) -> Self {
+        value.id
+    }
+
+    fn not_found(cf: &CurseForge, id: Self) -> Error {
+        Error::NotFound {
+            url: format!("{}/mods/files/{id}", cf.config.base_url),
+        }
+    }
+}
+
+type Waiters<T> = Vec<oneshot::Sender<Result<T>>>;
+
+#[derive(Debug)]
+struct Queue<K: Key> {
+    window: Duration,
+    pending: Mutex<HashMap<K, Waiters<K::Value>>>,
+}
+
+impl<K: Key> Queue<K> {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: Mutex::default(),
+        }
+    }
+
+    async fn load(self: &Arc<Self>, cf: &CurseForge, id: K) -> Result<K::Value> {
+        let (sender, receiver) = oneshot::channel();
+        let first = {
+            let mut pending = self.pending.lock().unwrap();
+            let first = pending.is_empty();
+            pending.entry(id).or_default().push(sender);
+            first
+        };
+        // The first lookup of a batch schedules it.
+        if first {
+            let queue = Arc::clone(self);
+            let cf = cf.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(queue.window).await;
+                queue.dispatch(&cf).await;
+            });
+        }
+        receiver.await.unwrap_or(Err(Error::Cancelled))
+    }
+
+    async fn dispatch(&self, cf: &CurseForge) {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let ids = pending.keys().copied().collect();
+        match K::fetch(cf, ids).await {
+            Ok(batch) => {
+                let found: HashMap<K, K::Value> = batch
+                    .found
+                    .into_iter()
+                    .map(|value| (K::id(&value), value))
+                    .collect();
+                for (id, waiters) in pending {
+                    for waiter in waiters {
+                        let result = match found.get(&id) {
+                            Some(value) => Ok(value.clone()),
+                            None => Err(K::not_found(cf, id)),
+                        };
+                        let _ = waiter.send(result);
+                    }
+                }
+            }
+            Err(err) => {
+                let err = Arc::new(err);
+                for waiter in pending.into_values().flatten() {
+                    let _ = waiter.send(Err(Error::Shared(Arc::clone(&err))));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct TestId(i32);
+
+    impl Key for TestId {
+        type Value = (TestId, usize);
+
+        /// Finds the even ids only, along with the number of ids requested.
+        async fn fetch(_: &CurseForge, ids: Vec<Self>) -> Result<Batch<Self, Self::Value>> {
+            REQUESTS.fetch_add(1, Ordering::Relaxed);
+            let (found, missing) = ids.iter().partition::<Vec<_>, _>(|id| id.0 % 2 == 0);
+            Ok(Batch {
+                found: found.into_iter().map(|id| (id, ids.len())).collect(),
+                missing,
+            })
+        }
+
+        fn id(value: &Self::Value) -> Self {
+            value.0
+        }
+
+        fn not_found(_: &CurseForge, id: Self) -> Error {
+            Error::NotFound {
+                url: id.0.to_string(),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct PanicId;
+
+    impl Key for PanicId {
+        type Value = PanicId;
+
+        async fn fetch(_: &CurseForge, _: Vec<Self>) -> Result<Batch<Self, Self>> {
+            panic!("lookup failed");
+        }
+
+        fn id(value: &Self) -> Self {
+            *value
+        }
+
+        fn not_found(_: &CurseForge, _: Self) -> Error {
+            unreachable!()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalesces_lookups() {
+        let cf = CurseForge::new("key").unwrap();
+        let queue = Arc::new(Queue::<TestId>::new(DEFAULT_WINDOW));
+        let (a, b, c) = tokio::join!(
+            queue.load(&cf, TestId(2)),
+            queue.load(&cf, TestId(2)),
+            queue.load(&cf, TestId(3)),
+        );
+        assert_eq!(REQUESTS.load(Ordering::Relaxed), 1);
+        assert_eq!(a.unwrap(), (TestId(2), 2));
+        assert_eq!(b.unwrap(), (TestId(2), 2));
+        assert!(matches!(c, Err(Error::NotFound { url }) if url == "3"));
+
+        queue.load(&cf, TestId(4)).await.unwrap();
+        assert_eq!(REQUESTS.load(Ordering::Relaxed), 2);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    struct RateLimitedId(i32);
+
+    impl Key for RateLimitedId {
+        type Value = RateLimitedId;
+
+        async fn fetch(_: &CurseForge, _: Vec<Self>) -> Result<Batch<Self, Self>> {
+            Err(Error::RateLimited { retry_after: None })
+        }
+
+        fn id(value: &Self) -> Self {
+            *value
+        }
+
+        fn not_found(_: &CurseForge, _: Self) -> Error {
+            unreachable!()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shares_batch_failures() {
+        let cf = CurseForge::new("key").unwrap();
+        let queue = Arc::new(Queue::<RateLimitedId>::new(DEFAULT_WINDOW));
+        let (a, b) = tokio::join!(
+            queue.load(&cf, RateLimitedId(1)),
+            queue.load(&cf, RateLimitedId(2)),
+        );
+        for result in [a, b] {
+            let err = result.unwrap_err();
+            assert!(matches!(err, Error::Shared(_)), "{err:?}");
+            assert!(matches!(err.root(), Error::RateLimited { .. }), "{err:?}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_cancelled_lookups() {
+        let cf = CurseForge::new("key").unwrap();
+        let queue = Arc::new(Queue::<PanicId>::new(DEFAULT_WINDOW));
+        assert!(matches!(
+            queue.load(&cf, PanicId).await,
+            Err(Error::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::CurseForge;
+use crate::definitions::{File, HashAlgo, Mod};
+use crate::download::{
+    BulkDownload, BulkDownloadOptions, DownloadLocation, FileDigests, hash_path, mismatch,
+};
+use crate::error::{Error, IntegrityCheck, Result};
+use crate::ids::{FileId, ModId};
+use crate::install::install_path;
+use crate::modpack::is_relative_path;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Exact files of a mod set, enough to rebuild it identically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockfile {
+    pub version: u32,
+    pub files: Vec<LockedFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedFile {
+    pub mod_id: ModId,
+    pub slug: String,
+    pub file_id: FileId,
+    pub file_name: String,
+    /// Where the file is installed, `/`-separated and relative to the instance folder.
+    pub path: String,
+    pub length: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
+    pub fingerprint: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct VerifiedFile {
+    pub locked: LockedFile,
+    pub status: LockStatus,
+}
+
+#[derive(Debug)]
+pub enum LockStatus {
+    Valid,
+    Missing,
+    /// The file on disk differs from the locked one.
+    Mismatch(Error),
+}
+
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// Files already matching the lockfile.
+    pub valid: Vec<LockedFile>,
+    /// Downloads of the missing and mismatching files, failed ones included.
+    pub downloads: Vec<BulkDownload>,
+    /// Files the API did not return or no longer matching the lockfile, they were not fetched.
+    pub unavailable: Vec<LockedFile>,
+}
+
+impl Lockfile {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let body = fs::read(path)?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+        let lockfile: Self = serde_path_to_error::deserialize(deserializer)
+            .map_err(|err| Error::decode(err, &body))?;
+        lockfile.validate()?;
+        Ok(lockfile)
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut body = serde_json::to_vec_pretty(self).map_err(std::io::Error::from)?;
+        body.push(b'\n');
+        Ok(fs::write(path, body)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.version != LOCKFILE_VERSION {
+            return Err(Error::InvalidLockfile(format!(
+                "unsupported version {}",
+                self.version
+            )));
+        }
+        if let Some(file) = self
+            .files
+            .iter()
+            .find(|file| !is_relative_path(Path::new(&file.path)))
+        {
+            return Err(Error::InvalidLockfile(format!(
+                "invalid path `{}`",
+                file.path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks every locked file against the instance folder `dir`.
+    pub async fn verify(&self, dir: impl AsRef<Path>) -> Vec<VerifiedFile> {
+        let dir = dir.as_ref();
+        let mut verified = Vec::with_capacity(self.files.len());
+        for locked in &self.files {
+            let path = locked.full_path(dir);
+            let status = if !path.is_file() {
+                LockStatus::Missing
+            } else {
+                match hash_path(&path)
+                    .await
+                    .and_then(|digests| locked.check(&digests))
+                {
+                    Ok(()) => LockStatus::Valid,
+                    Err(err) => LockStatus::Mismatch(err),
+                }
+            };
+            verified.push(VerifiedFile {
+                locked: locked.clone(),
+                status,
+            });
+        }
+        verified
+    }
+}
+
+impl LockedFile {
+    pub fn full_path(&self, dir: &Path) -> PathBuf {
+        dir.join(&self.path)
+    }
+
+    /// Checks digests of a file on disk against the locked ones.
+    fn check(&self, digests: &FileDigests) -> Result<()> {
+        if digests.length != self.length {
+            return Err(mismatch(
+                IntegrityCheck::Length,
+                self.length,
+                digests.length,
+            ));
+        }
+        if let Some(sha1) = &self.sha1
+            && !sha1.eq_ignore_ascii_case(&digests.sha1)
+        {
+            return Err(mismatch(IntegrityCheck::Sha1, sha1, &digests.sha1));
+        }
+        if digests.fingerprint != self.fingerprint {
+            return Err(mismatch(
+                IntegrityCheck::Fingerprint,
+                self.fingerprint,
+                digests.fingerprint,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the API still advertises the locked file.
+    fn matches(&self, file: &File) -> bool {
+        let sha1 = file
+            .hashes
+            .iter()
+            .find(|hash| hash.algo == HashAlgo::Sha1)
+            .map(|hash| hash.value.as_str());
+        file.id == self.file_id
+            && file.file_length as u64 == self.length
+            && file.file_fingerprint == i64::from(self.fingerprint)
+            && match (sha1, &self.sha1) {
+                (Some(actual), Some(expected)) => actual.eq_ignore_ascii_case(expected),
+                _ => true,
+            }
+    }
+}
+
+impl CurseForge {
+    /// Builds a lockfile from resolved files, fetching their mods for slugs and install folders.
+    pub async fn lock_files(&self, files: &[File]) -> Result<Lockfile> {
+        let mod_ids: Vec<ModId> = files.iter().map(|file| file.mod_id).collect();
+        let mods: HashMap<ModId, Mod> = (self.get_mods_all(&mod_ids).await?.found)
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect();
+
+        let mut locked = Vec::with_capacity(files.len());
+        for file in files {
+            let Some(project) = mods.get(&file.mod_id) else {
+                return Err(Error::NotFound {
+                    url: format!("{}/mods/{}", self.config.base_url, file.mod_id),
+                });
+            };
+            let path = install_path(file, project);
+            locked.push(LockedFile {
+                mod_id: file.mod_id,
+                slug: project.slug.clone(),
+                file_id: file.id,
+                file_name: file.file_name.clone().unwrap_or_default(),
+                path: path
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                length: file.file_length as u64,
+                sha1: file
+                    .hashes
+                    .iter()
+                    .find(|hash| hash.algo == HashAlgo::Sha1)
+                    .map(|hash| hash.value.to_lowercase()),
+                fingerprint: file.file_fingerprint as u32,
+                download_url: DownloadLocation::of(file, project)
+                    .url()
+                    .map(str::to_string),
+            });
+        }
+        locked.sort_by_key(|file| (file.mod_id, file.file_id));
+
+        Ok(Lockfile {
+            version: LOCKFILE_VERSION,
+            files: locked,
+        })
+    }
+
+    /// Verifies the instance folder `dir` against a lockfile and downloads every missing or
+    /// mismatching file again, as long as the API still serves the exact locked file.
+    pub async fn restore_lockfile(
+        &self,
+        lockfile: &Lockfile,
+        dir: impl AsRef<Path>,
+    ) -> Result<RestoreReport> {
+        let dir = dir.as_ref();
+        let mut report = RestoreReport::default();
+        let mut stale = Vec::new();
+        for verified in lockfile.verify(dir).await {
+            match verified.status {
+                LockStatus::Valid => report.valid.push(verified.locked),
+                LockStatus::Missing | LockStatus::Mismatch(_) => stale.push(verified.locked),
+            }
+        }
+
+        let file_ids: Vec<FileId> = stale.iter().map(|locked| locked.file_id).collect();
+        let mut files: HashMap<FileId, File> = (self.get_files_all(&file_ids).await?.found)
+            .into_iter()
+            .map(|f| (f.id, f))
+            .collect();
+
+        let mut downloads = Vec::new();
+        for locked in stale {
+            match files.remove(&locked.file_id) {
+                Some(file) if locked.matches(&file) => {
+                    downloads.push((file, locked.full_path(dir)));
+                }
+                _ => report.unavailable.push(locked),
+            }
+        }
+        let options = BulkDownloadOptions {
+            skip_existing: false,
+            ..Default::default()
+        };
+        report.downloads = self.download_files(downloads, &options).await;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::download::FileHasher;
+
+    fn locked(content: &[u8]) -> LockedFile {
+        let mut hasher = FileHasher::default();
+        hasher.update(content);
+        let digests = hasher.finish(crate::fingerprint::compute_fingerprint(content));
+        LockedFile {
+            mod_id: ModId(238222),
+            slug: "jei".to_string(),
+            file_id: FileId(4712866),
+            file_name: "jei.jar".to_string(),
+            path: "mods/jei.jar".to_string(),
+            length: digests.length,
+            sha1: Some(digests.sha1),
+            fingerprint: digests.fingerprint,
+            download_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn verifies_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let lockfile = Lockfile {
+            version: LOCKFILE_VERSION,
+            files: vec![
+                locked(b"jar content"),
+                LockedFile {
+                    path: "mods/other.jar".to_string(),
+                    ..locked(b"other")
+                },
+            ],
+        };
+        fs::create_dir(dir.path().join("mods")).unwrap();
+        fs::write(dir.path().join("mods/jei.jar"), b"jar content").unwrap();
+
+        let verified = lockfile.verify(dir.path()).await;
+        assert!(matches!(verified[0].status, LockStatus::Valid));
+        assert!(matches!(verified[1].status, LockStatus::Missing));
+
+        fs::write(dir.path().join("mods/jei.jar"), b"jar contenT").unwrap();
+        let verified = lockfile.verify(dir.path()).await;
+        assert!(matches!(
+            verified[0].status,
+            LockStatus::Mismatch(Error::Integrity {
+                check: IntegrityCheck::Sha1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn round_trips_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mods.lock.json");
+        let lockfile = Lockfile {
+            version: LOCKFILE_VERSION,
+            files: vec![locked(b"jar content")],
+        };
+        lockfile.write(&path).unwrap();
+        assert_eq!(Lockfile::read(&path).unwrap(), lockfile);
+
+        let escaping = Lockfile {
+            version: LOCKFILE_VERSION,
+            files: vec![LockedFile {
+                path: "../jei.jar".to_string(),
+                ..locked(b"")
+            }],
+        };
+        escaping.write(&path).unwrap();
+        assert!(matches!(
+            Lockfile::read(&path),
+            Err(Error::InvalidLockfile(_))
+        ));
+    }
+}
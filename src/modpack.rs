@@ -0,0 +1,271 @@
+use crate::CurseForge;
+use crate::definitions::{File, Mod};
+use crate::error::{Error, Result};
+use crate::ids::{FileId, ModId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use zip::ZipArchive;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+pub const MANIFEST_TYPE: &str = "minecraftModpack";
+pub const MANIFEST_VERSION: i32 = 1;
+
+// ============================================================================
+// MANIFEST STRUCTS
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModpackManifest {
+    pub minecraft: ManifestMinecraft,
+    pub manifest_type: String,
+    pub manifest_version: i32,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    pub files: Vec<ManifestFile>,
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestMinecraft {
+    pub version: String,
+    #[serde(default)]
+    pub mod_loaders: Vec<ManifestModLoader>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recommended_ram: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestModLoader {
+    /// Loader and version, e.g. `forge-47.2.0` or `fabric-0.15.11`.
+    pub id: String,
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    #[serde(rename = "projectID")]
+    pub project_id: ModId,
+    #[serde(rename = "fileID")]
+    pub file_id: FileId,
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl ModpackManifest {
+    pub fn validate(&self) -> Result<()> {
+        if self.manifest_type != MANIFEST_TYPE {
+            return Err(invalid(format!(
+                "unsupported manifest type `{}`",
+                self.manifest_type
+            )));
+        }
+        if self.manifest_version != MANIFEST_VERSION {
+            return Err(invalid(format!(
+                "unsupported manifest version {}",
+                self.manifest_version
+            )));
+        }
+        if self.minecraft.version.is_empty() {
+            return Err(invalid("missing Minecraft version".to_string()));
+        }
+        if !is_relative_path(Path::new(&self.overrides)) {
+            return Err(invalid(format!(
+                "invalid overrides folder `{}`",
+                self.overrides
+            )));
+        }
+        if let Some(file) = self
+            .files
+            .iter()
+            .find(|file| file.project_id.0 <= 0 || file.file_id.0 <= 0)
+        {
+            return Err(invalid(format!(
+                "invalid file entry {}/{}",
+                file.project_id, file.file_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// MODPACK ARCHIVE
+// ============================================================================
+
+/// A CurseForge modpack zip along with its validated manifest.
+#[derive(Debug, Clone)]
+pub struct Modpack {
+    pub path: PathBuf,
+    pub manifest: ModpackManifest,
+}
+
+impl Modpack {
+    /// Opens a modpack zip and reads and validates its `manifest.json`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let manifest = read_manifest(fs::File::open(&path)?)?;
+        Ok(Self { path, manifest })
+    }
+}
+
+/// Reads and validates the `manifest.json` of a modpack zip.
+pub fn read_manifest<R: Read + Seek>(reader: R) -> Result<ModpackManifest> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut entry = match archive.by_name(MANIFEST_FILE_NAME) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => {
+            return Err(invalid(format!("missing {MANIFEST_FILE_NAME}")));
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let mut body = Vec::new();
+    entry.read_to_end(&mut body)?;
+
+    let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+    let manifest: ModpackManifest =
+        serde_path_to_error::deserialize(deserializer).map_err(|err| Error::decode(err, &body))?;
+    manifest.validate()?;
+    Ok(manifest)
+}
+
+pub(crate) fn is_relative_path(path: &Path) -> bool {
+    path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn invalid(reason: String) -> Error {
+    Error::InvalidModpack(reason)
+}
+
+// ============================================================================
+// RESOLUTION
+// ============================================================================
+
+#[derive(Debug, Clone)]
+pub struct ResolvedFile {
+    pub entry: ManifestFile,
+    pub file: File,
+    pub project: Mod,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedModpack {
+    /// Resolved entries, in manifest order.
+    pub files: Vec<ResolvedFile>,
+    /// Entries whose file or mod was not returned by the API.
+    pub missing: Vec<ManifestFile>,
+}
+
+impl CurseForge {
+    /// Fetches the `File` and `Mod` of every entry of a manifest in batched requests.
+    pub async fn resolve_modpack(&self, manifest: &ModpackManifest) -> Result<ResolvedModpack> {
+        let file_ids: Vec<FileId> = manifest.files.iter().map(|entry| entry.file_id).collect();
+        let mod_ids: Vec<ModId> = manifest
+            .files
+            .iter()
+            .map(|entry| entry.project_id)
+            .collect();
+
+        let files: HashMap<FileId, File> = (self.get_files_all(&file_ids).await?.found)
+            .into_iter()
+            .map(|f| (f.id, f))
+            .collect();
+        let mods: HashMap<ModId, Mod> = (self.get_mods_all(&mod_ids).await?.found)
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect();
+
+        let mut resolved = ResolvedModpack::default();
+        for entry in &manifest.files {
+            match (files.get(&entry.file_id), mods.get(&entry.project_id)) {
+                (Some(file), Some(project)) => resolved.files.push(ResolvedFile {
+                    entry: entry.clone(),
+                    file: file.clone(),
+                    project: project.clone(),
+                }),
+                _ => resolved.missing.push(entry.clone()),
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    const MANIFEST: &str = r#"{
+        "minecraft": {
+            "version": "1.20.1",
+            "modLoaders": [{ "id": "forge-47.2.0", "primary": true }]
+        },
+        "manifestType": "minecraftModpack",
+        "manifestVersion": 1,
+        "name": "Example Pack",
+        "version": "1.0.0",
+        "author": "someone",
+        "files": [
+            { "projectID": 238222, "fileID": 4712866, "required": true },
+            { "projectID": 306612, "fileID": 4712867 }
+        ],
+        "overrides": "overrides"
+    }"#;
+
+    fn zip_with(name: &str, content: &str) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(name, SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        let mut cursor = writer.finish().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn reads_manifest() {
+        let manifest = read_manifest(zip_with(MANIFEST_FILE_NAME, MANIFEST)).unwrap();
+        assert_eq!(manifest.minecraft.version, "1.20.1");
+        assert_eq!(manifest.minecraft.mod_loaders[0].id, "forge-47.2.0");
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.files[1].required);
+    }
+
+    #[test]
+    fn rejects_invalid_packs() {
+        assert!(matches!(
+            read_manifest(zip_with("other.json", MANIFEST)),
+            Err(Error::InvalidModpack(_))
+        ));
+
+        let escaping = MANIFEST.replace(r#""overrides": "overrides""#, r#""overrides": "../..""#);
+        assert!(matches!(
+            read_manifest(zip_with(MANIFEST_FILE_NAME, &escaping)),
+            Err(Error::InvalidModpack(_))
+        ));
+    }
+}
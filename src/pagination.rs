@@ -0,0 +1,270 @@
+use crate::CurseForge;
+use crate::definitions::{
+    File, Game, GetGamesParams, GetModFilesParams, Mod, Pagination, SearchModsParams,
+};
+use crate::error::Result;
+use crate::ids::ModId;
+use futures::{Stream, TryStreamExt, stream};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// The API refuses requests where `index + page_size` is above this value.
+pub const MAX_INDEX: i32 = 10_000;
+/// Largest page size accepted by the API.
+pub const MAX_PAGE_SIZE: i32 = 50;
+/// Number of requests in flight when a helper fans out over many mods, files or batches.
+pub(crate) const CONCURRENT_REQUESTS: usize = 8;
+
+/// A page of results along with the pagination returned by the API.
+pub(crate) type Page<T> = (Vec<T>, Pagination);
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamOptions {
+    /// Fetch the next page in the background while the current one is consumed.
+    pub prefetch: bool,
+}
+
+impl CurseForge {
+    /// Streams every mod matching `params`, starting at `params.index`.
+    ///
+    /// The stream ends after `total_count` results or when [`MAX_INDEX`] is reached.
+    pub fn search_mods_stream(
+        &self,
+        params: &SearchModsParams,
+        options: StreamOptions,
+    ) -> impl Stream<Item = Result<Mod>> + Send + 'static {
+        let cf = self.clone();
+        let params = params.clone();
+        let (index, page_size) = (params.index, params.page_size);
+        paginate(index, page_size, options, move |index, page_size| {
+            let cf = cf.clone();
+            let params = SearchModsParams {
+                index: Some(index),
+                page_size: Some(page_size),
+                ..params.clone()
+            };
+            async move {
+                let response = cf.search_mods(&params).await?;
+                Ok((response.data, response.pagination))
+            }
+        })
+    }
+
+    /// Streams every file of a mod matching `params`, starting at `params.index`.
+    pub fn get_mod_files_stream(
+        &self,
+        mod_id: ModId,
+        params: &GetModFilesParams,
+        options: StreamOptions,
+    ) -> impl Stream<Item = Result<File>> + Send + 'static {
+        let cf = self.clone();
+        let params = params.clone();
+        let (index, page_size) = (params.index, params.page_size);
+        paginate(index, page_size, options, move |index, page_size| {
+            let cf = cf.clone();
+            let params = GetModFilesParams {
+                index: Some(index),
+                page_size: Some(page_size),
+                ..params.clone()
+            };
+            async move {
+                let response = cf.get_mod_files(mod_id, &params).await?;
+                Ok((response.data, response.pagination))
+            }
+        })
+    }
+
+    /// Streams every game available to the API key, starting at `params.index`.
+    pub fn get_games_stream(
+        &self,
+        params: &GetGamesParams,
+        options: StreamOptions,
+    ) -> impl Stream<Item = Result<Game>> + Send + 'static {
+        let cf = self.clone();
+        paginate(
+            params.index,
+            params.page_size,
+            options,
+            move |index, page_size| {
+                let cf = cf.clone();
+                let params = GetGamesParams {
+                    index: Some(index),
+                    page_size: Some(page_size),
+                };
+                async move {
+                    let response = cf.get_games(&params).await?;
+                    Ok((response.data, response.pagination))
+                }
+            },
+        )
+    }
+}
+
+struct PageState<F, T> {
+    fetch: Arc<F>,
+    next_index: Option<i32>,
+    page_size: i32,
+    prefetch: bool,
+    prefetched: Option<Prefetch<T>>,
+}
+
+/// A page fetched in the background, aborted if the stream is dropped before it is awaited so
+/// the request stops using rate limiter tokens.
+struct Prefetch<T>(JoinHandle<Result<Page<T>>>);
+
+impl<T> Drop for Prefetch<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Walks the pages returned by `fetch(index, page_size)` and flattens them into a stream of items.
+pub(crate) fn paginate<T, F, Fut>(
+    index: Option<i32>,
+    page_size: Option<i32>,
+    options: StreamOptions,
+    fetch: F,
+) -> impl Stream<Item = Result<T>> + Send + 'static
+where
+    T: Send + 'static,
+    F: Fn(i32, i32) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Page<T>>> + Send + 'static,
+{
+    let index = index.unwrap_or(0).max(0);
+    let state = PageState {
+        fetch: Arc::new(fetch),
+        next_index: (index < MAX_INDEX).then_some(index),
+        page_size: page_size.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+        prefetch: options.prefetch,
+        prefetched: None,
+    };
+
+    stream::try_unfold(state, next_page)
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+async fn next_page<T, F, Fut>(
+    mut state: PageState<F, T>,
+) -> Result<Option<(Vec<T>, PageState<F, T>)>>
+where
+    T: Send + 'static,
+    F: Fn(i32, i32) -> Fut,
+    Fut: Future<Output = Result<Page<T>>> + Send + 'static,
+{
+    let Some(index) = state.next_index else {
+        return Ok(None);
+    };
+    let (items, pagination) = match state.prefetched.take() {
+        Some(mut prefetch) => (&mut prefetch.0)
+            .await
+            .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))?,
+        None => (state.fetch)(index, page_size_at(index, state.page_size)).await?,
+    };
+
+    let next_index = index + items.len() as i32;
+    state.next_index = (!items.is_empty()
+        && i64::from(next_index) < pagination.total_count
+        && next_index < MAX_INDEX)
+        .then_some(next_index);
+
+    if let (Some(next_index), true) = (state.next_index, state.prefetch) {
+        let page = (state.fetch)(next_index, page_size_at(next_index, state.page_size));
+        state.prefetched = Some(Prefetch(tokio::spawn(page)));
+    }
+
+    Ok(Some((items, state)))
+}
+
+fn page_size_at(index: i32, page_size: i32) -> i32 {
+    page_size.min(MAX_INDEX - index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use futures::future::{BoxFuture, FutureExt};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn fake_pages(total: i64) -> impl Fn(i32, i32) -> futures::future::Ready<Result<Page<i32>>> {
+        move |index, page_size| {
+            let end = (i64::from(index + page_size)).min(total) as i32;
+            let items: Vec<i32> = (index..end).collect();
+            futures::future::ready(Ok((
+                items.clone(),
+                Pagination {
+                    index,
+                    page_size,
+                    result_count: items.len() as i32,
+                    total_count: total,
+                },
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_at_total_count() {
+        for prefetch in [false, true] {
+            let options = StreamOptions { prefetch };
+            let items: Vec<i32> = paginate(Some(2), Some(3), options, fake_pages(10))
+                .map(|item| item.unwrap())
+                .collect()
+                .await;
+            assert_eq!(items, (2..10).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn stops_at_index_cap() {
+        let options = StreamOptions::default();
+        let items: Vec<i32> = paginate(Some(9_990), Some(50), options, fake_pages(20_000))
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        assert_eq!(items, (9_990..MAX_INDEX).collect::<Vec<_>>());
+
+        // Nothing is requested past the cap.
+        let fetch = |_, _| -> futures::future::Ready<Result<Page<i32>>> { unreachable!() };
+        let mut stream = Box::pin(paginate(Some(MAX_INDEX), None, options, fetch));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn aborts_prefetch_on_drop() {
+        /// Sets its flag when the future holding it is dropped.
+        struct DropFlag(Arc<AtomicBool>);
+        impl Drop for DropFlag {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&dropped);
+        let fetch = move |index, page_size| -> BoxFuture<'static, Result<Page<i32>>> {
+            if index == 0 {
+                return fake_pages(100)(index, page_size).boxed();
+            }
+            let guard = DropFlag(Arc::clone(&flag));
+            async move {
+                let _guard = guard;
+                futures::future::pending().await
+            }
+            .boxed()
+        };
+        let options = StreamOptions { prefetch: true };
+        let items: Vec<i32> = paginate(None, Some(10), options, fetch)
+            .take(3)
+            .map(|item| item.unwrap())
+            .collect()
+            .await;
+        assert_eq!(items, [0, 1, 2]);
+
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert!(dropped.load(Ordering::Relaxed));
+    }
+}
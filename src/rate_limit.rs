@@ -0,0 +1,143 @@
+use crate::error::{Error, Result};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// Client-side limits applied to every API request.
+///
+/// Requests are spread using a token bucket refilled at `requests_per_second` that holds at most
+/// `burst` tokens. The limiter is shared by every clone of the client it is configured on.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    pub requests_per_second: f64,
+    pub burst: u32,
+    /// Maximum number of requests in flight at the same time.
+    pub max_concurrent: Option<usize>,
+}
+
+impl RateLimit {
+    pub fn per_second(requests_per_second: f64) -> Self {
+        Self {
+            requests_per_second,
+            burst: requests_per_second.ceil().max(1.0) as u32,
+            max_concurrent: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+    in_flight: Option<Arc<Semaphore>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Keeps a concurrency slot reserved until the request completes.
+pub(crate) struct RatePermit {
+    _in_flight: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limit: &RateLimit) -> Result<Self> {
+        let rate = limit.requests_per_second;
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(Error::Config(format!(
+                "Invalid rate limit of {rate} requests per second"
+            )));
+        }
+        let burst = f64::from(limit.burst.max(1));
+        Ok(Self {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated_at: Instant::now(),
+            }),
+            in_flight: limit
+                .max_concurrent
+                .map(|max| Arc::new(Semaphore::new(max.max(1)))),
+        })
+    }
+
+    /// Waits until a request may be sent.
+    pub(crate) async fn acquire(&self) -> RatePermit {
+        let in_flight = match &self.in_flight {
+            Some(semaphore) => Some(
+                Arc::clone(semaphore)
+                    .acquire_owned()
+                    .await
+                    .expect("rate limiter semaphore is never closed"),
+            ),
+            None => None,
+        };
+
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+
+        RatePermit {
+            _in_flight: in_flight,
+        }
+    }
+
+    /// Takes a token from the bucket, letting it go negative so waiters are served in order, and
+    /// returns how long the caller has to wait for its token to be refilled.
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|err| err.into_inner());
+        let now = Instant::now();
+        let refilled = now.duration_since(bucket.updated_at).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refilled).min(self.burst) - 1.0;
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_then_steady_rate() {
+        let limiter = RateLimiter::new(&RateLimit {
+            requests_per_second: 10.0,
+            burst: 2,
+            max_concurrent: None,
+        })
+        .unwrap();
+
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(), Duration::from_millis(200));
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let limiter = RateLimiter::new(&RateLimit::per_second(rate));
+            assert!(matches!(limiter, Err(Error::Config(_))), "{rate}");
+        }
+        let built = crate::CurseForge::builder()
+            .api_key("key")
+            .rate_limit(RateLimit::per_second(0.0))
+            .build();
+        assert!(matches!(built, Err(Error::Config(_))));
+    }
+}
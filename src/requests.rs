@@ -1,213 +1,490 @@
+use crate::CurseForge;
+use crate::cache::{Endpoint, Freshness};
 use crate::definitions::{
-    GetCategoriesParams, GetCategoriesResponse, GetFeaturedModsRequestBody, GetFeaturedModsResponse, GetFilesResponse,
-    GetFingerprintMatchesRequestBody, GetFingerprintMatchesResponse, GetFingerprintsFuzzyMatchesResponse,
-    GetFuzzyMatchesRequestBody, GetGameResponse, GetGamesParams,
-    GetGamesResponse, GetMinecraftModLoaderResponse, GetMinecraftModLoadersParams, GetMinecraftModLoadersResponse,
-    GetMinecraftVersionResponse, GetMinecraftVersionsParams, GetMinecraftVersionsResponse,
-    GetModDescriptionParams, GetModFileResponse, GetModFilesParams,
-    GetModFilesRequestBody, GetModFilesResponse, GetModResponse, GetModsRequestBody,
-    GetModsResponse, GetVersionTypesResponse, GetVersionsResponse, GetVersionsResponseV1,
-    SearchModsParams, SearchModsResponse, StringResponse, CF_URL,
-    CF_V2_URL,
+    ApiResponse, GetCategoriesParams, GetCategoriesResponse, GetFeaturedModsRequestBody,
+    GetFeaturedModsResponse, GetFilesResponse, GetFingerprintMatchesRequestBody,
+    GetFingerprintMatchesResponse, GetFingerprintsFuzzyMatchesResponse, GetFuzzyMatchesRequestBody,
+    GetGameResponse, GetGamesParams, GetGamesResponse, GetMinecraftModLoaderResponse,
+    GetMinecraftModLoadersParams, GetMinecraftModLoadersResponse, GetMinecraftVersionResponse,
+    GetMinecraftVersionsParams, GetMinecraftVersionsResponse, GetModDescriptionParams,
+    GetModFileResponse, GetModFilesParams, GetModFilesRequestBody, GetModFilesResponse,
+    GetModResponse, GetModsRequestBody, GetModsResponse, GetVersionTypesResponse,
+    GetVersionsResponse, GetVersionsResponseV1, SearchModsParams, SearchModsResponse,
+    StringResponse,
 };
-use crate::CurseForge;
-use anyhow::Result;
-use const_format::concatcp;
-use serde::de::DeserializeOwned;
+use crate::error::{Error, Result};
+use crate::ids::{FileId, GameId, ModId};
+use bytes::Bytes;
+use reqwest::header::USER_AGENT;
+use reqwest::{Method, RequestBuilder, Response};
 use serde::Serialize;
+use serde::de::{DeserializeOwned, IgnoredAny};
 
 impl CurseForge {
     pub async fn get_games(&self, params: &GetGamesParams) -> Result<GetGamesResponse> {
-        self.get(concatcp!(CF_URL, "/games"), params).await
+        let url = format!("{}/games", self.config.base_url);
+        self.get(Endpoint::Games, &url, params).await
     }
 
-    pub async fn get_game(&self, game_id: i32) -> Result<GetGameResponse> {
-        let url = format!("{CF_URL}/games/{game_id}");
-        self.get(&url, &()).await
+    pub async fn get_game(&self, game_id: GameId) -> Result<GetGameResponse> {
+        let url = format!("{}/games/{game_id}", self.config.base_url);
+        self.get(Endpoint::Games, &url, &()).await
     }
 
     #[deprecated(note = "Use get_versions instead")]
-    pub async fn get_versions_v1(&self, game_id: i32) -> Result<GetVersionsResponseV1> {
-        let url = format!("{CF_URL}/games/{game_id}/versions");
-        self.get(&url, &()).await
+    pub async fn get_versions_v1(&self, game_id: GameId) -> Result<GetVersionsResponseV1> {
+        let url = format!("{}/games/{game_id}/versions", self.config.base_url);
+        self.get(Endpoint::Versions, &url, &()).await
     }
 
-    pub async fn get_version_types(&self, game_id: i32) -> Result<GetVersionTypesResponse> {
-        let url = format!("{CF_URL}/games/{game_id}/version-types");
-        self.get(&url, &()).await
+    pub async fn get_version_types(&self, game_id: GameId) -> Result<GetVersionTypesResponse> {
+        let url = format!("{}/games/{game_id}/version-types", self.config.base_url);
+        self.get(Endpoint::VersionTypes, &url, &()).await
     }
 
-    pub async fn get_versions(&self, game_id: i32) -> Result<GetVersionsResponse> {
-        let url = format!("{CF_V2_URL}/games/{game_id}/versions");
-        self.get(&url, &()).await
+    pub async fn get_versions(&self, game_id: GameId) -> Result<GetVersionsResponse> {
+        let url = format!("{}/games/{game_id}/versions", self.config.base_url_v2);
+        self.get(Endpoint::Versions, &url, &()).await
     }
 
     pub async fn get_categories(
         &self,
         params: &GetCategoriesParams,
     ) -> Result<GetCategoriesResponse> {
-        self.get(concatcp!(CF_URL, "/categories"), params).await
+        let url = format!("{}/categories", self.config.base_url);
+        self.get(Endpoint::Categories, &url, params).await
     }
 
     pub async fn search_mods(&self, params: &SearchModsParams) -> Result<SearchModsResponse> {
-        self.get(concatcp!(CF_URL, "/mods/search"), params).await
+        let url = format!("{}/mods/search", self.config.base_url);
+        self.get(Endpoint::Search, &url, params).await
     }
 
-    pub async fn get_mod(&self, mod_id: i32) -> Result<GetModResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}");
-        self.get(&url, &()).await
+    pub async fn get_mod(&self, mod_id: ModId) -> Result<GetModResponse> {
+        let url = format!("{}/mods/{mod_id}", self.config.base_url);
+        self.get(Endpoint::Mods, &url, &()).await
     }
 
     pub async fn get_mods(&self, body: &GetModsRequestBody) -> Result<GetModsResponse> {
-        self.post(concatcp!(CF_URL, "/mods"), body).await
+        let url = format!("{}/mods", self.config.base_url);
+        self.post(Endpoint::Mods, &url, body).await
     }
 
     pub async fn get_featured_mods(
         &self,
         body: &GetFeaturedModsRequestBody,
     ) -> Result<GetFeaturedModsResponse> {
-        self.post(concatcp!(CF_URL, "/mods/featured"), body).await
+        let url = format!("{}/mods/featured", self.config.base_url);
+        self.post(Endpoint::FeaturedMods, &url, body).await
     }
 
     pub async fn get_mod_description(
         &self,
-        mod_id: i32,
+        mod_id: ModId,
         params: &GetModDescriptionParams,
     ) -> Result<StringResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}/description");
-        self.get(&url, &params).await
+        let url = format!("{}/mods/{mod_id}/description", self.config.base_url);
+        self.get(Endpoint::ModDescriptions, &url, &params).await
     }
 
-    pub async fn get_mod_file(&self, mod_id: i32, file_id: i32) -> Result<GetModFileResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}/files/{file_id}");
-        self.get(&url, &()).await
+    pub async fn get_mod_file(&self, mod_id: ModId, file_id: FileId) -> Result<GetModFileResponse> {
+        let url = format!("{}/mods/{mod_id}/files/{file_id}", self.config.base_url);
+        self.get(Endpoint::Files, &url, &()).await
     }
 
     pub async fn get_mod_files(
         &self,
-        mod_id: i32,
+        mod_id: ModId,
         params: &GetModFilesParams,
     ) -> Result<GetModFilesResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}/files");
-        self.get(&url, &params).await
+        let url = format!("{}/mods/{mod_id}/files", self.config.base_url);
+        self.get(Endpoint::Files, &url, &params).await
     }
 
     pub async fn get_files(&self, body: &GetModFilesRequestBody) -> Result<GetFilesResponse> {
-        self.post(concatcp!(CF_URL, "/mods/files"), body).await
+        let url = format!("{}/mods/files", self.config.base_url);
+        self.post(Endpoint::Files, &url, body).await
     }
 
     pub async fn get_mod_files_changelog(
         &self,
-        mod_id: i32,
-        file_id: i32,
+        mod_id: ModId,
+        file_id: FileId,
     ) -> Result<StringResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}/files/{file_id}/changelog");
-        self.get(&url, &()).await
+        let url = format!(
+            "{}/mods/{mod_id}/files/{file_id}/changelog",
+            self.config.base_url
+        );
+        self.get(Endpoint::Changelogs, &url, &()).await
     }
 
     pub async fn get_mod_file_download_url(
         &self,
-        mod_id: i32,
-        file_id: i32,
+        mod_id: ModId,
+        file_id: FileId,
     ) -> Result<StringResponse> {
-        let url = format!("{CF_URL}/mods/{mod_id}/files/{file_id}/download-url");
-        self.get(&url, &()).await
+        let url = format!(
+            "{}/mods/{mod_id}/files/{file_id}/download-url",
+            self.config.base_url
+        );
+        self.get(Endpoint::DownloadUrls, &url, &()).await
     }
 
     pub async fn get_fingerprints_matches_by_game_id(
         &self,
-        game_id: i32,
+        game_id: GameId,
         body: &GetFingerprintMatchesRequestBody,
     ) -> Result<GetFingerprintMatchesResponse> {
-        let url = format!("{CF_URL}/fingerprints/{game_id}");
-        self.post(&url, body).await
+        let url = format!("{}/fingerprints/{game_id}", self.config.base_url);
+        self.post(Endpoint::Fingerprints, &url, body).await
     }
 
     pub async fn get_fingerprints_matches(
         &self,
         body: &GetFingerprintMatchesRequestBody,
     ) -> Result<GetFingerprintMatchesResponse> {
-        self.post(concatcp!(CF_URL, "/fingerprints"), body).await
+        let url = format!("{}/fingerprints", self.config.base_url);
+        self.post(Endpoint::Fingerprints, &url, body).await
     }
 
     pub async fn get_fingerprints_fuzzy_matches_by_game_id(
         &self,
-        game_id: i32,
+        game_id: GameId,
         body: &GetFuzzyMatchesRequestBody,
     ) -> Result<GetFingerprintsFuzzyMatchesResponse> {
-        let url = format!("{CF_URL}/fingerprints/fuzzy/{game_id}");
-        self.post(&url, body).await
+        let url = format!("{}/fingerprints/fuzzy/{game_id}", self.config.base_url);
+        self.post(Endpoint::Fingerprints, &url, body).await
     }
 
     pub async fn get_fingerprints_fuzzy_matches(
         &self,
         body: &GetFuzzyMatchesRequestBody,
     ) -> Result<GetFingerprintsFuzzyMatchesResponse> {
-        self.post(concatcp!(CF_URL, "/fingerprints/fuzzy"), body)
-            .await
+        let url = format!("{}/fingerprints/fuzzy", self.config.base_url);
+        self.post(Endpoint::Fingerprints, &url, body).await
     }
 
     pub async fn get_minecraft_versions(
         &self,
         params: &GetMinecraftVersionsParams,
     ) -> Result<GetMinecraftVersionsResponse> {
-        self.get(concatcp!(CF_URL, "/minecraft/versions"), params)
-            .await
+        let url = format!("{}/minecraft/versions", self.config.base_url);
+        self.get(Endpoint::MinecraftVersions, &url, params).await
     }
 
     pub async fn get_minecraft_version(
         &self,
         version: &str,
     ) -> Result<GetMinecraftVersionResponse> {
-        let url = format!("{CF_URL}/minecraft/versions/{version}");
-        self.get(&url, &()).await
+        let url = format!("{}/minecraft/versions/{version}", self.config.base_url);
+        self.get(Endpoint::MinecraftVersions, &url, &()).await
     }
 
     pub async fn get_minecraft_mod_loaders(
         &self,
         params: &GetMinecraftModLoadersParams,
     ) -> Result<GetMinecraftModLoadersResponse> {
-        self.get(concatcp!(CF_URL, "/minecraft/modloader"), params)
-            .await
+        let url = format!("{}/minecraft/modloader", self.config.base_url);
+        self.get(Endpoint::MinecraftModLoaders, &url, params).await
     }
 
     pub async fn get_minecraft_mod_loader(
         &self,
         mod_loader: &str,
     ) -> Result<GetMinecraftModLoaderResponse> {
-        let url = format!("{CF_URL}/minecraft/modloader/{mod_loader}");
-        self.get(&url, &()).await
+        let url = format!("{}/minecraft/modloader/{mod_loader}", self.config.base_url);
+        self.get(Endpoint::MinecraftModLoaders, &url, &()).await
     }
 
-    async fn get<P, R>(&self, url: &str, params: &P) -> Result<R>
+    async fn get<P, R>(&self, endpoint: Endpoint, url: &str, params: &P) -> Result<R>
     where
         P: Serialize + ?Sized,
-        R: DeserializeOwned,
+        R: ApiResponse,
     {
-        Ok(self
-            .client
-            .get(url)
-            .query(params)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<R>()
-            .await?)
+        let request = self.request(Method::GET, url).query(params);
+        self.fetch(endpoint, request).await
     }
 
-    async fn post<B, R>(&self, url: &str, body: &B) -> Result<R>
+    /// Every POST endpoint of the API is a read-only query, so they are safe to retry and cache.
+    /// A POST with side effects must not go through here.
+    async fn post<B, R>(&self, endpoint: Endpoint, url: &str, body: &B) -> Result<R>
     where
         B: Serialize + ?Sized,
-        R: DeserializeOwned,
+        R: ApiResponse,
     {
-        Ok(self
+        let request = self.request(Method::POST, url).json(body);
+        self.fetch(endpoint, request).await
+    }
+
+    /// Serves the request from the caches if possible, otherwise sends it and caches the
+    /// response. Expired cached responses are served stale when the API can't be reached.
+    async fn fetch<R: ApiResponse>(
+        &self,
+        endpoint: Endpoint,
+        request: RequestBuilder,
+    ) -> Result<R> {
+        let layers = self.caches.layers();
+        let key = (!layers.is_empty()).then(|| cache_key(&request)).flatten();
+        let Some(key) = key else {
+            return decode(&self.execute(request).await?);
+        };
+
+        let (mut stale, mut fallback) = (None, None);
+        for (index, cache) in layers.iter().enumerate() {
+            let Some(cached) = cache.get(&key, endpoint).await else {
+                continue;
+            };
+            match cached.freshness {
+                Freshness::Fresh => {
+                    if let Ok(data) = decode(&cached.body) {
+                        // Fill the faster caches that missed.
+                        for cache in &layers[..index] {
+                            cache.put(&key, endpoint, cached.body.clone()).await;
+                        }
+                        return Ok(data);
+                    }
+                }
+                Freshness::Revalidate => {
+                    stale.get_or_insert(cached.body);
+                }
+                Freshness::Expired => {
+                    fallback.get_or_insert(cached.body);
+                }
+            }
+        }
+        if let Some(data) = stale.and_then(|body| decode_stale(&body).ok()) {
+            self.revalidate(key, endpoint, request);
+            return Ok(data);
+        }
+
+        match self.execute(request).await {
+            Ok(body) => {
+                let data = decode(&body)?;
+                self.store(&key, endpoint, body).await;
+                Ok(data)
+            }
+            Err(err @ (Error::Transport(_) | Error::Server { .. })) => fallback
+                .and_then(|body| decode_stale(&body).ok())
+                .ok_or(err),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Refreshes a cached response in the background.
+    fn revalidate(&self, key: String, endpoint: Endpoint, request: RequestBuilder) {
+        let cf = self.clone();
+        tokio::spawn(async move {
+            if let Ok(body) = cf.execute(request).await
+                && serde_json::from_slice::<IgnoredAny>(&body).is_ok()
+            {
+                cf.store(&key, endpoint, body).await;
+            }
+        });
+    }
+
+    async fn store(&self, key: &str, endpoint: Endpoint, body: Bytes) {
+        for cache in self.caches.layers() {
+            cache.put(key, endpoint, body.clone()).await;
+        }
+    }
+
+    /// Sends the request, retrying it per the retry policy. Only use it for idempotent requests.
+    async fn execute(&self, mut request: RequestBuilder) -> Result<Bytes> {
+        let mut attempt = 1;
+        loop {
+            let retry = request.try_clone();
+            let permit = match &self.rate_limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
+            let result = match request.send().await {
+                Ok(response) => Self::read(response).await,
+                Err(err) => Err(err.into()),
+            };
+            drop(permit);
+            let err = match result {
+                Ok(body) => return Ok(body),
+                Err(err) => err,
+            };
+
+            let (Some(retry), Some(delay)) =
+                (retry, self.config.retry_policy.delay_for(&err, attempt))
+            else {
+                return Err(err);
+            };
+            tokio::time::sleep(delay).await;
+            request = retry;
+            attempt += 1;
+        }
+    }
+
+    async fn read(response: Response) -> Result<Bytes> {
+        if !response.status().is_success() {
+            return Err(Error::from_response(response).await);
+        }
+        Ok(response.bytes().await?)
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self
             .client
-            .post(url)
-            .json(body)
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<R>()
-            .await?)
+            .request(method, url)
+            .header("x-api-key", self.config.api_key.clone());
+        if let Some(user_agent) = &self.config.user_agent {
+            request = request.header(USER_AGENT, user_agent.clone());
+        }
+        if let Some(timeout) = self.config.timeout {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+}
+
+fn decode<R: DeserializeOwned>(body: &[u8]) -> Result<R> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(deserializer).map_err(|err| Error::decode(err, body))
+}
+
+fn decode_stale<R: ApiResponse>(body: &[u8]) -> Result<R> {
+    let mut data: R = decode(body)?;
+    data.mark_stale();
+    Ok(data)
+}
+
+/// Identifies a request by method, URL with its query, and body.
+fn cache_key(request: &RequestBuilder) -> Option<String> {
+    let request = request.try_clone()?.build().ok()?;
+    let body = request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .unwrap_or_default();
+    Some(format!(
+        "{} {}\n{}",
+        request.method(),
+        request.url(),
+        String::from_utf8_lossy(body)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{Cache, CacheConfig, CachedResponse};
+    use crate::fixtures::{Response, TestServer, UNREACHABLE_URL};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// A cache answering every key with the same response and recording what is stored.
+    #[derive(Debug, Default)]
+    struct TestCache {
+        response: Mutex<Option<CachedResponse>>,
+        stored: Mutex<Vec<Bytes>>,
+    }
+
+    impl TestCache {
+        fn with(body: &'static str, freshness: Freshness) -> Arc<Self> {
+            Arc::new(Self {
+                response: Mutex::new(Some(CachedResponse {
+                    body: Bytes::from_static(body.as_bytes()),
+                    freshness,
+                })),
+                stored: Mutex::default(),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Cache for Arc<TestCache> {
+        async fn get(&self, _: &str, _: Endpoint) -> Option<CachedResponse> {
+            self.response.lock().unwrap().clone()
+        }
+
+        async fn put(&self, _: &str, _: Endpoint, body: Bytes) {
+            self.stored.lock().unwrap().push(body);
+        }
+
+        async fn invalidate(&self, _: Option<Endpoint>) {}
+    }
+
+    fn serve(body: &'static str) -> TestServer {
+        TestServer::start(move |_| Response::new(200, body))
+    }
+
+    fn client(base_url: &str, cache: &Arc<TestCache>) -> CurseForge {
+        CurseForge::builder()
+            .api_key("key")
+            .base_url(base_url)
+            .cache(CacheConfig::default())
+            .cache_backend(Arc::clone(cache))
+            .build()
+            .unwrap()
+    }
+
+    async fn changelog(cf: &CurseForge) -> Result<StringResponse> {
+        cf.get_mod_files_changelog(ModId(1), FileId(2)).await
+    }
+
+    #[tokio::test]
+    async fn fills_faster_caches_on_hit() {
+        let cache = TestCache::with(r#"{"data": "cached"}"#, Freshness::Fresh);
+        let cf = client(UNREACHABLE_URL, &cache);
+
+        let response = changelog(&cf).await.unwrap();
+        assert_eq!((response.data.as_str(), response.stale), ("cached", false));
+        assert_eq!(cf.cache_stats().unwrap().entries, 1);
+        assert!(cache.stored.lock().unwrap().is_empty());
+
+        // Served from memory from now on.
+        *cache.response.lock().unwrap() = None;
+        assert_eq!(changelog(&cf).await.unwrap().data, "cached");
+        assert_eq!(cf.cache_stats().unwrap().hits, 1);
+    }
+
+    #[tokio::test]
+    async fn serves_expired_only_when_unreachable() {
+        let cache = TestCache::with(r#"{"data": "expired"}"#, Freshness::Expired);
+        let response = changelog(&client(UNREACHABLE_URL, &cache)).await.unwrap();
+        assert_eq!((response.data.as_str(), response.stale), ("expired", true));
+
+        let server = serve(r#"{"data": "live"}"#);
+        let response = changelog(&client(&server.url, &cache)).await.unwrap();
+        assert_eq!((response.data.as_str(), response.stale), ("live", false));
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(
+            cache.stored.lock().unwrap().as_slice(),
+            [r#"{"data": "live"}"#]
+        );
+
+        // Nothing to fall back on.
+        let cache = Arc::new(TestCache::default());
+        let result = changelog(&client(UNREACHABLE_URL, &cache)).await;
+        assert!(matches!(result, Err(Error::Transport(_))), "{result:?}");
+    }
+
+    #[tokio::test]
+    async fn revalidates_in_background() {
+        let cache = TestCache::with(r#"{"data": "stale"}"#, Freshness::Revalidate);
+        let server = serve(r#"{"data": "live"}"#);
+        let cf = client(&server.url, &cache);
+
+        let response = changelog(&cf).await.unwrap();
+        assert_eq!((response.data.as_str(), response.stale), ("stale", true));
+
+        for _ in 0..100 {
+            if !cache.stored.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(
+            cache.stored.lock().unwrap().as_slice(),
+            [r#"{"data": "live"}"#]
+        );
+        // The refreshed response is served from memory.
+        *cache.response.lock().unwrap() = None;
+        let response = changelog(&cf).await.unwrap();
+        assert_eq!((response.data.as_str(), response.stale), ("live", false));
     }
 }
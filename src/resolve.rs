@@ -0,0 +1,338 @@
+use crate::CurseForge;
+use crate::definitions::{
+    File, FileRelationType, FileReleaseType, GetModFilesParams, ModLoaderType,
+};
+use crate::error::{Error, Result};
+use crate::ids::ModId;
+use crate::pagination::{CONCURRENT_REQUESTS, MAX_PAGE_SIZE};
+use futures::{StreamExt, TryStreamExt};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct ResolveOptions {
+    pub game_version: String,
+    /// Loader the files must support, [`ModLoaderType::Any`] to not filter by loader.
+    pub mod_loader: ModLoaderType,
+    /// Also pull in optional dependencies.
+    pub include_optional: bool,
+}
+
+/// Why a mod is part of an install plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InclusionReason {
+    Root,
+    RequiredBy(ModId),
+    OptionalFor(ModId),
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedMod {
+    pub mod_id: ModId,
+    pub file: File,
+    pub reason: InclusionReason,
+}
+
+/// Two mods of a plan, the file of `mod_id` declares `incompatible_with` as incompatible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conflict {
+    pub mod_id: ModId,
+    pub incompatible_with: ModId,
+}
+
+/// A mod no compatible file was found for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnresolvedMod {
+    pub mod_id: ModId,
+    pub reason: InclusionReason,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstallPlan {
+    /// Mods to install, every mod comes after its dependencies unless they form a cycle.
+    pub mods: Vec<PlannedMod>,
+    pub conflicts: Vec<Conflict>,
+    /// Dependency cycles, as the mod ids along the cycle.
+    pub cycles: Vec<Vec<ModId>>,
+    pub unresolved: Vec<UnresolvedMod>,
+}
+
+impl InstallPlan {
+    /// Whether the plan can be installed as is: no conflict and nothing unresolved.
+    pub fn is_complete(&self) -> bool {
+        self.conflicts.is_empty() && self.unresolved.is_empty()
+    }
+}
+
+impl CurseForge {
+    /// Resolves `roots` and their dependencies, recursively, into an install plan.
+    ///
+    /// For every mod the newest file supporting the game version and loader is picked, releases
+    /// being preferred over betas and alphas. Required dependencies are always followed, optional
+    /// ones only if [`ResolveOptions::include_optional`] is set. Embedded libraries, includes and
+    /// tools are not followed.
+    pub async fn resolve_dependencies(
+        &self,
+        roots: &[ModId],
+        options: &ResolveOptions,
+    ) -> Result<InstallPlan> {
+        let mut reasons: HashMap<ModId, InclusionReason> = HashMap::new();
+        let mut files: HashMap<ModId, File> = HashMap::new();
+        let mut unresolved = Vec::new();
+
+        let mut level: Vec<ModId> = Vec::new();
+        for &mod_id in roots {
+            if reasons.insert(mod_id, InclusionReason::Root).is_none() {
+                level.push(mod_id);
+            }
+        }
+
+        while !level.is_empty() {
+            let picked: Vec<(ModId, Option<File>)> = futures::stream::iter(level)
+                .map(|mod_id| async move {
+                    Ok::<_, Error>((mod_id, self.compatible_file(mod_id, options).await?))
+                })
+                .buffered(CONCURRENT_REQUESTS)
+                .try_collect()
+                .await?;
+
+            let mut next = Vec::new();
+            for (mod_id, file) in picked {
+                let Some(file) = file else {
+                    unresolved.push(UnresolvedMod {
+                        mod_id,
+                        reason: reasons[&mod_id],
+                    });
+                    continue;
+                };
+                for dependency in &file.dependencies {
+                    let reason = match dependency.relation_type {
+                        FileRelationType::RequiredDependency => InclusionReason::RequiredBy(mod_id),
+                        FileRelationType::OptionalDependency if options.include_optional => {
+                            InclusionReason::OptionalFor(mod_id)
+                        }
+                        _ => continue,
+                    };
+                    if let Entry::Vacant(entry) = reasons.entry(dependency.mod_id) {
+                        entry.insert(reason);
+                        next.push(dependency.mod_id);
+                    }
+                }
+                files.insert(mod_id, file);
+            }
+            level = next;
+        }
+
+        let mut plan = plan(roots, files, &reasons, options.include_optional);
+        plan.unresolved = unresolved;
+        Ok(plan)
+    }
+
+    /// The best file of a mod for the options, `None` if it has none or doesn't exist.
+    async fn compatible_file(
+        &self,
+        mod_id: ModId,
+        options: &ResolveOptions,
+    ) -> Result<Option<File>> {
+        let params = GetModFilesParams {
+            game_version: Some(options.game_version.clone()),
+            mod_loader_type: Some(options.mod_loader).filter(|l| *l != ModLoaderType::Any),
+            page_size: Some(MAX_PAGE_SIZE),
+            ..Default::default()
+        };
+        match self.get_mod_files(mod_id, &params).await {
+            Ok(response) => Ok(pick_file(response.data, &options.game_version)),
+            Err(Error::NotFound { .. }) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Picks the newest available file for the game version, preferring the most stable ones.
+fn pick_file(files: Vec<File>, game_version: &str) -> Option<File> {
+    files
+        .into_iter()
+        .filter(|file| file.is_available)
+        .filter(|file| file.game_versions.iter().any(|v| v == game_version))
+        .min_by_key(|file| (stability(file.release_type), Reverse(file.file_date)))
+}
+
+pub(crate) fn stability(release_type: FileReleaseType) -> u8 {
+    match release_type {
+        FileReleaseType::Release => 0,
+        FileReleaseType::Beta => 1,
+        FileReleaseType::Alpha => 2,
+    }
+}
+
+/// Orders the resolved files so dependencies come first, and collects conflicts and cycles.
+fn plan(
+    roots: &[ModId],
+    mut files: HashMap<ModId, File>,
+    reasons: &HashMap<ModId, InclusionReason>,
+    include_optional: bool,
+) -> InstallPlan {
+    let edges = |file: &File| -> Vec<ModId> {
+        file.dependencies
+            .iter()
+            .filter(|dependency| match dependency.relation_type {
+                FileRelationType::RequiredDependency => true,
+                FileRelationType::OptionalDependency => include_optional,
+                _ => false,
+            })
+            .map(|dependency| dependency.mod_id)
+            .collect()
+    };
+
+    let mut order = Vec::new();
+    let mut cycles = Vec::new();
+    let mut done = HashSet::new();
+    let mut visiting: Vec<ModId> = Vec::new();
+    // Depth-first post-order, the stack holds each mod with the dependencies left to visit.
+    for &root in roots {
+        let mut stack: Vec<(ModId, Vec<ModId>)> = Vec::new();
+        if let Some(file) = files.get(&root)
+            && !done.contains(&root)
+        {
+            let mut pending = edges(file);
+            pending.reverse();
+            stack.push((root, pending));
+            visiting.push(root);
+        }
+        while let Some((mod_id, pending)) = stack.last_mut() {
+            let mod_id = *mod_id;
+            let Some(dependency) = pending.pop() else {
+                stack.pop();
+                visiting.pop();
+                done.insert(mod_id);
+                order.push(mod_id);
+                continue;
+            };
+            if done.contains(&dependency) {
+                continue;
+            }
+            if let Some(start) = visiting.iter().position(|id| *id == dependency) {
+                cycles.push(visiting[start..].to_vec());
+                continue;
+            }
+            if let Some(file) = files.get(&dependency) {
+                let mut pending = edges(file);
+                pending.reverse();
+                stack.push((dependency, pending));
+                visiting.push(dependency);
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for mod_id in &order {
+        for dependency in &files[mod_id].dependencies {
+            if dependency.relation_type == FileRelationType::Incompatible
+                && done.contains(&dependency.mod_id)
+            {
+                conflicts.push(Conflict {
+                    mod_id: *mod_id,
+                    incompatible_with: dependency.mod_id,
+                });
+            }
+        }
+    }
+
+    InstallPlan {
+        mods: order
+            .into_iter()
+            .filter_map(|mod_id| {
+                Some(PlannedMod {
+                    mod_id,
+                    file: files.remove(&mod_id)?,
+                    reason: reasons[&mod_id],
+                })
+            })
+            .collect(),
+        conflicts,
+        cycles,
+        unresolved: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::FileDependency;
+    use crate::fixtures;
+    use crate::ids::FileId;
+    use serde_json::json;
+
+    fn file(mod_id: i32, dependencies: &[(i32, FileRelationType)]) -> File {
+        let mut file = fixtures::file(json!({
+            "id": mod_id * 10,
+            "modId": mod_id,
+            "gameVersions": ["1.20.1"],
+        }));
+        file.dependencies = dependencies
+            .iter()
+            .map(|&(mod_id, relation_type)| FileDependency {
+                mod_id: ModId(mod_id),
+                relation_type,
+            })
+            .collect();
+        file
+    }
+
+    #[test]
+    fn orders_dependencies_first_and_reports_cycles_and_conflicts() {
+        use FileRelationType::*;
+        let files: HashMap<ModId, File> = [
+            file(1, &[(2, RequiredDependency), (3, OptionalDependency)]),
+            file(2, &[(4, RequiredDependency), (5, Incompatible)]),
+            file(3, &[(4, RequiredDependency)]),
+            file(4, &[(2, RequiredDependency)]),
+            file(5, &[]),
+        ]
+        .into_iter()
+        .map(|file| (file.mod_id, file))
+        .collect();
+        let reasons = HashMap::from([
+            (ModId(1), InclusionReason::Root),
+            (ModId(2), InclusionReason::RequiredBy(ModId(1))),
+            (ModId(3), InclusionReason::OptionalFor(ModId(1))),
+            (ModId(4), InclusionReason::RequiredBy(ModId(2))),
+            (ModId(5), InclusionReason::Root),
+        ]);
+
+        let resolved = plan(&[ModId(1), ModId(5)], files.clone(), &reasons, false);
+        let order: Vec<i32> = resolved.mods.iter().map(|m| m.mod_id.0).collect();
+        assert_eq!(order, [4, 2, 1, 5]);
+        assert_eq!(resolved.cycles, [vec![ModId(2), ModId(4)]]);
+        assert_eq!(
+            resolved.conflicts,
+            [Conflict {
+                mod_id: ModId(2),
+                incompatible_with: ModId(5)
+            }]
+        );
+
+        let resolved = plan(&[ModId(1)], files, &reasons, true);
+        let order: Vec<i32> = resolved.mods.iter().map(|m| m.mod_id.0).collect();
+        assert_eq!(order, [4, 2, 3, 1]);
+        assert!(resolved.conflicts.is_empty());
+    }
+
+    #[test]
+    fn picks_most_stable_then_newest() {
+        let mut beta = file(1, &[]);
+        beta.release_type = FileReleaseType::Beta;
+        beta.file_date = "2024-06-01T00:00:00Z".parse().unwrap();
+        let mut old = file(1, &[]);
+        old.id = FileId(1);
+        old.file_date = "2023-01-01T00:00:00Z".parse().unwrap();
+        let release = file(1, &[]);
+        let mut other_version = file(1, &[]);
+        other_version.game_versions = vec!["1.19.2".to_string()];
+        other_version.file_date = "2025-01-01T00:00:00Z".parse().unwrap();
+
+        let picked = pick_file(vec![beta, old, release.clone(), other_version], "1.20.1");
+        assert_eq!(picked.map(|f| f.id), Some(release.id));
+    }
+}
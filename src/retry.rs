@@ -0,0 +1,131 @@
+use crate::error::Error;
+use reqwest::StatusCode;
+use std::time::Duration;
+
+/// Controls how failed requests are retried.
+///
+/// Delays grow exponentially from `base_delay` and are capped by `max_delay`. A `Retry-After`
+/// header sent with a 429 response replaces the computed delay, a request is not retried when
+/// the server asks to wait longer than `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of the delay that is randomized, between `0.0` and `1.0`.
+    pub jitter: f64,
+    pub retry_statuses: Vec<StatusCode>,
+    pub retry_on_timeout: bool,
+    pub retry_on_connect: bool,
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retry_statuses: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            retry_on_timeout: true,
+            retry_on_connect: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns how long to wait before the next attempt, or `None` if the error is final.
+    pub(crate) fn delay_for(&self, err: &Error, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.is_retryable(err) {
+            return None;
+        }
+
+        if let Error::RateLimited {
+            retry_after: Some(retry_after),
+        } = err
+            && self.respect_retry_after
+        {
+            return (*retry_after <= self.max_delay).then_some(*retry_after);
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * fastrand::f64();
+        Some(backoff.mul_f64(1.0 - jitter))
+    }
+
+    fn is_retryable(&self, err: &Error) -> bool {
+        match err {
+            Error::Transport(err) if err.is_timeout() => self.retry_on_timeout,
+            Error::Transport(err) if err.is_connect() => self.retry_on_connect,
+            Error::Decode { .. } | Error::Config(_) => false,
+            err => err
+                .status()
+                .is_some_and(|status| self.retry_statuses.contains(&status)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff_without_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let err = Error::Server {
+            status: StatusCode::BAD_GATEWAY,
+            body: String::new(),
+        };
+
+        assert_eq!(policy.delay_for(&err, 1), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay_for(&err, 2), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay_for(&err, 3), Some(Duration::from_millis(300)));
+        assert_eq!(policy.delay_for(&err, 4), None);
+    }
+
+    #[test]
+    fn retry_after_and_final_errors() {
+        let policy = RetryPolicy::default();
+        let throttled = Error::RateLimited {
+            retry_after: Some(Duration::from_secs(2)),
+        };
+        assert_eq!(
+            policy.delay_for(&throttled, 1),
+            Some(Duration::from_secs(2))
+        );
+
+        let too_long = Error::RateLimited {
+            retry_after: Some(Duration::from_secs(120)),
+        };
+        assert_eq!(policy.delay_for(&too_long, 1), None);
+
+        let not_found = Error::NotFound { url: String::new() };
+        assert_eq!(policy.delay_for(&not_found, 1), None);
+    }
+}
@@ -0,0 +1,521 @@
+use crate::CurseForge;
+use crate::definitions::{
+    GetCategoriesParams, Mod, ModLoaderType, ModsSearchSortField, SearchModsParams, SortOrder,
+};
+use crate::error::Result;
+use crate::ids::{CategoryId, ClassId, GameId, ModId};
+use crate::pagination::{MAX_INDEX, MAX_PAGE_SIZE, Page, StreamOptions};
+use futures::TryStreamExt;
+use std::collections::HashSet;
+
+const MOD_LOADERS: [ModLoaderType; 6] = [
+    ModLoaderType::Forge,
+    ModLoaderType::Cauldron,
+    ModLoaderType::LiteLoader,
+    ModLoaderType::Fabric,
+    ModLoaderType::Quilt,
+    ModLoaderType::NeoForge,
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct ExhaustiveSearch {
+    /// Every mod found, de-duplicated by id.
+    pub mods: Vec<Mod>,
+    /// Partitions that could not be enumerated under the index cap, their results are
+    /// incomplete.
+    pub incomplete: Vec<SearchModsParams>,
+}
+
+impl CurseForge {
+    /// Enumerates every mod of a game, see [`CurseForge::search_all_mods_with`].
+    pub async fn search_all_mods(&self, game_id: GameId) -> Result<ExhaustiveSearch> {
+        let params = SearchModsParams {
+            game_id,
+            ..Default::default()
+        };
+        self.search_all_mods_with(&params).await
+    }
+
+    /// Enumerates every mod matching `params`, getting past the 10,000 results index cap.
+    ///
+    /// Queries over the cap are partitioned by class, then category, then mod loader, then game
+    /// version until each partition fits. Partitions that still don't fit are read from both
+    /// ends by sorting on release date, which covers up to twice the cap. Mod loader and game
+    /// version partitions only reach mods that declare them, so when they don't add up to the
+    /// partition they split, that partition is also read from both ends. Partitions whose mods
+    /// could not all be found are listed in [`ExhaustiveSearch::incomplete`].
+    pub async fn search_all_mods_with(
+        &self,
+        params: &SearchModsParams,
+    ) -> Result<ExhaustiveSearch> {
+        Search::new(self, MAX_INDEX).run(params).await
+    }
+}
+
+/// The requests an exhaustive search is made of.
+trait SearchSource {
+    /// A single page of results, along with the total count.
+    async fn search(&self, params: &SearchModsParams) -> Result<Page<Mod>>;
+
+    /// Every result from `params.index` up to the index cap.
+    async fn collect(&self, params: &SearchModsParams) -> Result<Vec<Mod>>;
+
+    async fn classes(&self, game_id: GameId) -> Result<Vec<ClassId>>;
+
+    async fn categories(
+        &self,
+        game_id: GameId,
+        class_id: Option<ClassId>,
+    ) -> Result<Vec<CategoryId>>;
+
+    /// Names of every version of the game, possibly repeated.
+    async fn versions(&self, game_id: GameId) -> Result<Vec<String>>;
+}
+
+impl SearchSource for CurseForge {
+    async fn search(&self, params: &SearchModsParams) -> Result<Page<Mod>> {
+        let response = self.search_mods(params).await?;
+        Ok((response.data, response.pagination))
+    }
+
+    async fn collect(&self, params: &SearchModsParams) -> Result<Vec<Mod>> {
+        self.search_mods_stream(params, StreamOptions { prefetch: true })
+            .try_collect()
+            .await
+    }
+
+    async fn classes(&self, game_id: GameId) -> Result<Vec<ClassId>> {
+        let params = GetCategoriesParams {
+            game_id,
+            class_id: None,
+            classes_only: Some(true),
+        };
+        let categories = self.get_categories(&params).await?.data;
+        Ok(categories.iter().map(|class| ClassId(class.id.0)).collect())
+    }
+
+    async fn categories(
+        &self,
+        game_id: GameId,
+        class_id: Option<ClassId>,
+    ) -> Result<Vec<CategoryId>> {
+        let params = GetCategoriesParams {
+            game_id,
+            class_id,
+            classes_only: None,
+        };
+        let categories = self.get_categories(&params).await?.data;
+        Ok(categories
+            .iter()
+            .filter(|category| category.is_class != Some(true))
+            .map(|category| category.id)
+            .collect())
+    }
+
+    async fn versions(&self, game_id: GameId) -> Result<Vec<String>> {
+        Ok(self
+            .get_versions(game_id)
+            .await?
+            .data
+            .into_iter()
+            .flat_map(|by_type| by_type.versions)
+            .map(|version| version.name)
+            .collect())
+    }
+}
+
+enum Step {
+    Search(SearchModsParams),
+    /// Checks that the partitions of a lossy split covered the partition they split.
+    Check {
+        params: SearchModsParams,
+        total: i64,
+    },
+}
+
+/// A partition split by mod loader or game version, whose partitions are still being read.
+struct Lossy {
+    /// Ids of the mods found in the partition so far.
+    found: HashSet<ModId>,
+    /// Length of `incomplete` when the partition was split, later entries are inside it.
+    incomplete_from: usize,
+}
+
+struct Search<'a, S> {
+    source: &'a S,
+    /// Highest index reachable by a search.
+    cap: i32,
+    seen: HashSet<ModId>,
+    result: ExhaustiveSearch,
+    lossy: Vec<Lossy>,
+    classes: Option<Vec<ClassId>>,
+    versions: Option<Vec<String>>,
+}
+
+impl<'a, S: SearchSource> Search<'a, S> {
+    fn new(source: &'a S, cap: i32) -> Self {
+        Self {
+            source,
+            cap,
+            seen: HashSet::new(),
+            result: ExhaustiveSearch::default(),
+            lossy: Vec::new(),
+            classes: None,
+            versions: None,
+        }
+    }
+
+    async fn run(mut self, params: &SearchModsParams) -> Result<ExhaustiveSearch> {
+        let mut pending = vec![Step::Search(SearchModsParams {
+            index: None,
+            page_size: None,
+            ..params.clone()
+        })];
+
+        while let Some(step) = pending.pop() {
+            match step {
+                Step::Search(params) => {
+                    let (first_page, pagination) = self
+                        .source
+                        .search(&SearchModsParams {
+                            index: Some(0),
+                            page_size: Some(MAX_PAGE_SIZE.min(self.cap)),
+                            ..params.clone()
+                        })
+                        .await?;
+                    let total = pagination.total_count;
+                    let fetched = first_page.len();
+                    let first_ids: HashSet<ModId> = first_page.iter().map(|m| m.id).collect();
+                    self.add(first_page);
+
+                    if total <= i64::from(self.cap) {
+                        if total > fetched as i64 {
+                            let mods = self.collect(&params, fetched as i32).await?;
+                            self.add(mods);
+                        }
+                    } else if let Some((partitions, lossy)) = self.split(&params).await? {
+                        if lossy {
+                            self.lossy.push(Lossy {
+                                found: first_ids,
+                                incomplete_from: self.result.incomplete.len(),
+                            });
+                            pending.push(Step::Check {
+                                params: params.clone(),
+                                total,
+                            });
+                        }
+                        pending.extend(partitions.into_iter().rev().map(Step::Search));
+                    } else {
+                        self.collect_both_ends(&params, total, first_ids).await?;
+                    }
+                }
+                Step::Check { params, total } => {
+                    let Some(lossy) = self.lossy.pop() else {
+                        continue;
+                    };
+                    if (lossy.found.len() as i64) < total {
+                        // The partition covers the incomplete ones found inside it.
+                        let incomplete = self.result.incomplete.len();
+                        self.collect_both_ends(&params, total, lossy.found).await?;
+                        if self.result.incomplete.len() > incomplete {
+                            self.result
+                                .incomplete
+                                .drain(lossy.incomplete_from..incomplete);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(self.result)
+    }
+
+    fn add(&mut self, mods: Vec<Mod>) {
+        for m in mods {
+            for lossy in &mut self.lossy {
+                lossy.found.insert(m.id);
+            }
+            if self.seen.insert(m.id) {
+                self.result.mods.push(m);
+            }
+        }
+    }
+
+    async fn collect(&self, params: &SearchModsParams, index: i32) -> Result<Vec<Mod>> {
+        let params = SearchModsParams {
+            index: Some(index),
+            ..params.clone()
+        };
+        self.source.collect(&params).await
+    }
+
+    /// Reads a partition sorted by release date in both directions, `found` holds the ids of the
+    /// mods already found in it.
+    async fn collect_both_ends(
+        &mut self,
+        params: &SearchModsParams,
+        total: i64,
+        mut found: HashSet<ModId>,
+    ) -> Result<()> {
+        for sort_order in [SortOrder::Asc, SortOrder::Desc] {
+            let params = SearchModsParams {
+                sort_field: Some(ModsSearchSortField::ReleasedDate),
+                sort_order: Some(sort_order),
+                ..params.clone()
+            };
+            let mods = self.collect(&params, 0).await?;
+            found.extend(mods.iter().map(|m| m.id));
+            self.add(mods);
+        }
+
+        if (found.len() as i64) < total {
+            self.result.incomplete.push(params.clone());
+        }
+        Ok(())
+    }
+
+    /// Splits `params` along the first dimension it does not filter on yet. The split is lossy
+    /// when mods may be in none of the partitions.
+    async fn split(
+        &mut self,
+        params: &SearchModsParams,
+    ) -> Result<Option<(Vec<SearchModsParams>, bool)>> {
+        if params.class_id.is_none() {
+            let classes = self.classes(params.game_id).await?;
+            if !classes.is_empty() {
+                let partitions = classes
+                    .iter()
+                    .map(|&class_id| SearchModsParams {
+                        class_id: Some(class_id),
+                        ..params.clone()
+                    })
+                    .collect();
+                return Ok(Some((partitions, false)));
+            }
+        }
+
+        if params.category_id.is_none() && params.category_ids.is_none() {
+            let categories = self
+                .source
+                .categories(params.game_id, params.class_id)
+                .await?;
+            if !categories.is_empty() {
+                let partitions = categories
+                    .into_iter()
+                    .map(|category_id| SearchModsParams {
+                        category_id: Some(category_id),
+                        ..params.clone()
+                    })
+                    .collect();
+                return Ok(Some((partitions, false)));
+            }
+        }
+
+        if params.mod_loader_type.is_none() && params.mod_loader_types.is_none() {
+            let partitions = MOD_LOADERS
+                .iter()
+                .map(|&mod_loader_type| SearchModsParams {
+                    mod_loader_type: Some(mod_loader_type),
+                    ..params.clone()
+                })
+                .collect();
+            return Ok(Some((partitions, true)));
+        }
+
+        if params.game_version.is_none() && params.game_versions.is_none() {
+            let versions = self.versions(params.game_id).await?;
+            if !versions.is_empty() {
+                let partitions = versions
+                    .iter()
+                    .map(|version| SearchModsParams {
+                        game_version: Some(version.clone()),
+                        ..params.clone()
+                    })
+                    .collect();
+                return Ok(Some((partitions, true)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn classes(&mut self, game_id: GameId) -> Result<&[ClassId]> {
+        if self.classes.is_none() {
+            self.classes = Some(self.source.classes(game_id).await?);
+        }
+        Ok(self.classes.as_deref().unwrap_or_default())
+    }
+
+    async fn versions(&mut self, game_id: GameId) -> Result<&[String]> {
+        if self.versions.is_none() {
+            let mut seen = HashSet::new();
+            let versions = self.source.versions(game_id).await?;
+            self.versions = Some(
+                versions
+                    .into_iter()
+                    .filter(|name| seen.insert(name.clone()))
+                    .collect(),
+            );
+        }
+        Ok(self.versions.as_deref().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::Pagination;
+    use crate::fixtures;
+    use serde_json::json;
+
+    const CAP: i32 = 4;
+
+    struct FakeMod {
+        id: i32,
+        class: i32,
+        category: i32,
+        loaders: Vec<ModLoaderType>,
+        version: Option<&'static str>,
+    }
+
+    /// Searches over a fixed set of mods, with an index cap of `CAP`.
+    struct FakeSource {
+        mods: Vec<FakeMod>,
+    }
+
+    impl FakeSource {
+        fn matching(&self, params: &SearchModsParams) -> Vec<Mod> {
+            let mut mods: Vec<Mod> = self
+                .mods
+                .iter()
+                .filter(|m| params.class_id.is_none_or(|class| class.0 == m.class))
+                .filter(|m| {
+                    params
+                        .category_id
+                        .is_none_or(|category| category.0 == m.category)
+                })
+                .filter(|m| {
+                    params
+                        .mod_loader_type
+                        .is_none_or(|loader| m.loaders.contains(&loader))
+                })
+                .filter(|m| {
+                    params
+                        .game_version
+                        .as_deref()
+                        .is_none_or(|v| m.version == Some(v))
+                })
+                .map(|m| fixtures::project(json!({ "id": m.id })))
+                .collect();
+            if params.sort_order == Some(SortOrder::Desc) {
+                mods.reverse();
+            }
+            mods
+        }
+    }
+
+    impl SearchSource for FakeSource {
+        async fn search(&self, params: &SearchModsParams) -> Result<Page<Mod>> {
+            let mods = self.matching(params);
+            let total = mods.len() as i64;
+            let index = params.index.unwrap_or(0);
+            let page_size = params.page_size.unwrap_or(MAX_PAGE_SIZE);
+            assert!(index + page_size <= CAP, "index cap exceeded");
+            let page: Vec<Mod> = mods
+                .into_iter()
+                .skip(index as usize)
+                .take(page_size as usize)
+                .collect();
+            let pagination = Pagination {
+                index,
+                page_size,
+                result_count: page.len() as i32,
+                total_count: total,
+            };
+            Ok((page, pagination))
+        }
+
+        async fn collect(&self, params: &SearchModsParams) -> Result<Vec<Mod>> {
+            let index = params.index.unwrap_or(0) as usize;
+            Ok(self
+                .matching(params)
+                .into_iter()
+                .take(CAP as usize)
+                .skip(index)
+                .collect())
+        }
+
+        async fn classes(&self, _: GameId) -> Result<Vec<ClassId>> {
+            Ok(vec![ClassId(6), ClassId(12)])
+        }
+
+        async fn categories(
+            &self,
+            _: GameId,
+            class_id: Option<ClassId>,
+        ) -> Result<Vec<CategoryId>> {
+            Ok(match class_id {
+                Some(ClassId(6)) => vec![CategoryId(100), CategoryId(101)],
+                _ => vec![CategoryId(200)],
+            })
+        }
+
+        async fn versions(&self, _: GameId) -> Result<Vec<String>> {
+            Ok(["1.20", "1.21", "1.20"].map(String::from).to_vec())
+        }
+    }
+
+    fn fake_mods() -> Vec<FakeMod> {
+        use ModLoaderType::{Fabric, Forge};
+        let fake = |id, class, category, loaders: &[ModLoaderType], version| FakeMod {
+            id,
+            class,
+            category,
+            loaders: loaders.to_vec(),
+            version,
+        };
+        let mut mods = vec![
+            fake(1, 12, 200, &[], None),
+            fake(2, 12, 200, &[], None),
+            fake(3, 12, 200, &[], None),
+            // Over the cap, the mods without a loader are found from both ends.
+            fake(10, 6, 100, &[Forge], None),
+            fake(11, 6, 100, &[Forge], None),
+            fake(12, 6, 100, &[Forge, Fabric], None),
+            fake(13, 6, 100, &[Fabric], None),
+            fake(14, 6, 100, &[], None),
+            fake(15, 6, 100, &[], None),
+        ];
+        // Over the cap even for Forge, 26 and 27 have no version and are out of reach.
+        for id in 20..32 {
+            let version = match id {
+                20..23 => Some("1.20"),
+                23..26 => Some("1.21"),
+                _ => None,
+            };
+            mods.push(fake(id, 6, 101, &[Forge], version));
+        }
+        mods
+    }
+
+    #[tokio::test]
+    async fn partitions_under_the_cap() {
+        let source = FakeSource { mods: fake_mods() };
+        let params = SearchModsParams {
+            game_id: GameId::MINECRAFT,
+            ..Default::default()
+        };
+        let result = Search::new(&source, CAP).run(&params).await.unwrap();
+
+        let mut ids: Vec<i32> = result.mods.iter().map(|m| m.id.0).collect();
+        ids.sort_unstable();
+        let expected: Vec<i32> = (1..4).chain(10..16).chain(20..26).chain(28..32).collect();
+        assert_eq!(ids, expected);
+
+        // Only the category is reported, not the Forge partition inside it.
+        assert_eq!(result.incomplete.len(), 1);
+        let incomplete = &result.incomplete[0];
+        assert_eq!(incomplete.category_id, Some(CategoryId(101)));
+        assert_eq!(incomplete.mod_loader_type, None);
+    }
+}
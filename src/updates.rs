@@ -0,0 +1,306 @@
+use crate::CurseForge;
+use crate::definitions::{File, FileIndex, FileReleaseType, GetModFilesParams, ModLoaderType};
+use crate::error::Result;
+use crate::ids::{FileId, ModId};
+use crate::pagination::{CONCURRENT_REQUESTS, MAX_PAGE_SIZE};
+use crate::resolve::stability;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub mod_id: ModId,
+    pub installed_file_id: FileId,
+    pub status: UpdateStatus,
+}
+
+#[derive(Debug, Clone)]
+pub enum UpdateStatus {
+    UpToDate,
+    Available(Box<Update>),
+    /// The mod was not returned by the API.
+    NotFound,
+    /// A newer file is indexed but the API did not return it, so it can't be installed.
+    Unavailable {
+        file_id: FileId,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub file: File,
+    /// Changelog of the new file, as HTML. `None` if it couldn't be fetched.
+    pub changelog: Option<String>,
+}
+
+impl CurseForge {
+    /// Checks installed `(mod_id, file_id)` pairs for newer compatible files.
+    ///
+    /// A file is a candidate if it supports `game_version` and `mod_loader` and is at least as
+    /// stable as `min_release_type`, the newest candidate is an update if its id is greater than
+    /// the installed one. Candidates are looked up in [`Mod::latest_files_indexes`] first, mods
+    /// without any fall back to `get_mod_files`.
+    ///
+    /// [`Mod::latest_files_indexes`]: crate::definitions::Mod::latest_files_indexes
+    pub async fn check_updates(
+        &self,
+        installed: &[(ModId, FileId)],
+        game_version: &str,
+        mod_loader: ModLoaderType,
+        min_release_type: FileReleaseType,
+    ) -> Result<Vec<UpdateCheck>> {
+        let mut mod_ids: Vec<ModId> = installed.iter().map(|(mod_id, _)| *mod_id).collect();
+        mod_ids.sort_unstable();
+        mod_ids.dedup();
+
+        let indexes: HashMap<ModId, _> = (self.get_mods_all(&mod_ids).await?.found)
+            .into_iter()
+            .map(|m| (m.id, m.latest_files_indexes))
+            .collect();
+
+        // Newest candidate of every mod, from its indexes or from its files.
+        let mut latest: HashMap<ModId, FileId> = HashMap::new();
+        let mut fallback = Vec::new();
+        for (mod_id, indexes) in &indexes {
+            match best_index(indexes, game_version, mod_loader, min_release_type) {
+                Some(file_id) => {
+                    latest.insert(*mod_id, file_id);
+                }
+                None => fallback.push(*mod_id),
+            }
+        }
+        let mut files: HashMap<FileId, File> = HashMap::new();
+        let fetched: Vec<Option<File>> = futures::stream::iter(fallback)
+            .map(|mod_id| self.latest_file(mod_id, game_version, mod_loader, min_release_type))
+            .buffered(CONCURRENT_REQUESTS)
+            .try_collect()
+            .await?;
+        for file in fetched.into_iter().flatten() {
+            latest.insert(file.mod_id, file.id);
+            files.insert(file.id, file);
+        }
+
+        let mut updates: Vec<(ModId, FileId)> = installed
+            .iter()
+            .filter_map(|(mod_id, file_id)| {
+                let latest = *latest.get(mod_id)?;
+                (latest > *file_id).then_some((*mod_id, latest))
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        updates.sort_unstable();
+
+        let missing: Vec<FileId> = updates
+            .iter()
+            .map(|(_, file_id)| *file_id)
+            .filter(|file_id| !files.contains_key(file_id))
+            .collect();
+        let fetched = self.get_files_all(&missing).await?.found;
+        files.extend(fetched.into_iter().map(|f| (f.id, f)));
+        updates.retain(|(_, file_id)| files.contains_key(file_id));
+
+        // A changelog failing to load leaves it out instead of failing the whole check.
+        let changelogs: HashMap<FileId, String> = futures::stream::iter(updates)
+            .map(|(mod_id, file_id)| async move {
+                let changelog = self.get_mod_files_changelog(mod_id, file_id).await;
+                changelog.ok().map(|changelog| (file_id, changelog.data))
+            })
+            .buffered(CONCURRENT_REQUESTS)
+            .filter_map(std::future::ready)
+            .collect()
+            .await;
+
+        Ok(installed
+            .iter()
+            .map(|&(mod_id, installed_file_id)| {
+                let status = if !indexes.contains_key(&mod_id) {
+                    UpdateStatus::NotFound
+                } else {
+                    let latest = latest.get(&mod_id).copied();
+                    update_status(installed_file_id, latest, &files, &changelogs)
+                };
+                UpdateCheck {
+                    mod_id,
+                    installed_file_id,
+                    status,
+                }
+            })
+            .collect())
+    }
+
+    /// The newest compatible file of a mod, looked up among its files.
+    async fn latest_file(
+        &self,
+        mod_id: ModId,
+        game_version: &str,
+        mod_loader: ModLoaderType,
+        min_release_type: FileReleaseType,
+    ) -> Result<Option<File>> {
+        let params = GetModFilesParams {
+            game_version: Some(game_version.to_string()),
+            mod_loader_type: Some(mod_loader).filter(|l| *l != ModLoaderType::Any),
+            page_size: Some(MAX_PAGE_SIZE),
+            ..Default::default()
+        };
+        let files = self.get_mod_files(mod_id, &params).await?.data;
+        Ok(files
+            .into_iter()
+            .filter(|file| file.is_available)
+            .filter(|file| is_stable_enough(file.release_type, min_release_type))
+            .filter(|file| file.game_versions.iter().any(|v| v == game_version))
+            .max_by_key(|file| file.id))
+    }
+}
+
+/// Status of an installed file given the newest candidate of its mod.
+fn update_status(
+    installed_file_id: FileId,
+    latest: Option<FileId>,
+    files: &HashMap<FileId, File>,
+    changelogs: &HashMap<FileId, String>,
+) -> UpdateStatus {
+    let Some(latest) = latest.filter(|latest| *latest > installed_file_id) else {
+        return UpdateStatus::UpToDate;
+    };
+    match files.get(&latest) {
+        Some(file) => UpdateStatus::Available(Box::new(Update {
+            file: file.clone(),
+            changelog: changelogs.get(&latest).cloned(),
+        })),
+        None => UpdateStatus::Unavailable { file_id: latest },
+    }
+}
+
+/// Id of the newest indexed file matching the game version, loader and stability.
+fn best_index(
+    indexes: &[FileIndex],
+    game_version: &str,
+    mod_loader: ModLoaderType,
+    min_release_type: FileReleaseType,
+) -> Option<FileId> {
+    indexes
+        .iter()
+        .filter(|index| index.game_version == game_version)
+        .filter(|index| mod_loader == ModLoaderType::Any || index.mod_loader == Some(mod_loader))
+        .filter(|index| is_stable_enough(index.release_type, min_release_type))
+        .map(|index| index.file_id)
+        .max()
+}
+
+fn is_stable_enough(release_type: FileReleaseType, min_release_type: FileReleaseType) -> bool {
+    stability(release_type) <= stability(min_release_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, Response, TestServer};
+    use serde_json::json;
+
+    fn index(file_id: i32, release_type: FileReleaseType, mod_loader: ModLoaderType) -> FileIndex {
+        FileIndex {
+            game_version: "1.20.1".to_string(),
+            file_id: FileId(file_id),
+            filename: format!("{file_id}.jar"),
+            release_type,
+            game_version_type_id: None,
+            mod_loader: Some(mod_loader),
+        }
+    }
+
+    #[test]
+    fn picks_newest_matching_index() {
+        use FileReleaseType::*;
+        use ModLoaderType::*;
+        let indexes = [
+            index(10, Release, Forge),
+            index(12, Beta, Forge),
+            index(13, Release, Fabric),
+            index(14, Alpha, Forge),
+        ];
+        assert_eq!(
+            best_index(&indexes, "1.20.1", Forge, Release),
+            Some(FileId(10))
+        );
+        assert_eq!(
+            best_index(&indexes, "1.20.1", Forge, Beta),
+            Some(FileId(12))
+        );
+        assert_eq!(
+            best_index(&indexes, "1.20.1", Forge, Alpha),
+            Some(FileId(14))
+        );
+        assert_eq!(
+            best_index(&indexes, "1.20.1", Any, Release),
+            Some(FileId(13))
+        );
+        assert_eq!(best_index(&indexes, "1.19.2", Forge, Alpha), None);
+    }
+
+    #[test]
+    fn reports_unfetched_updates() {
+        let file = fixtures::file(json!({ "id": 12, "fileName": "12.jar" }));
+        let files = HashMap::from([(FileId(12), file)]);
+        let changelogs = HashMap::from([(FileId(12), "Fixes".to_string())]);
+
+        let status =
+            |installed, latest| update_status(FileId(installed), latest, &files, &changelogs);
+        assert!(
+            matches!(status(10, Some(FileId(12))), UpdateStatus::Available(update)
+            if update.file.id == FileId(12) && update.changelog.as_deref() == Some("Fixes"))
+        );
+        assert!(matches!(
+            status(12, Some(FileId(12))),
+            UpdateStatus::UpToDate
+        ));
+        assert!(matches!(status(10, None), UpdateStatus::UpToDate));
+        assert!(matches!(
+            status(10, Some(FileId(13))),
+            UpdateStatus::Unavailable {
+                file_id: FileId(13)
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_updates_whose_changelog_fails() {
+        let server = TestServer::start(|request| {
+            let body = match request.path.as_str() {
+                "/mods" => json!({"data": [fixtures::project(json!({
+                    "id": 1,
+                    "latestFilesIndexes": [index(12, FileReleaseType::Release, ModLoaderType::Forge)],
+                }))]}),
+                "/mods/files" => json!({"data": [fixtures::file(json!({"id": 12, "modId": 1}))]}),
+                _ => return Response::new(500, ""),
+            };
+            Response::new(200, body.to_string())
+        });
+        let cf = CurseForge::builder()
+            .api_key("key")
+            .base_url(&server.url)
+            .build()
+            .unwrap();
+
+        let checks = cf
+            .check_updates(
+                &[(ModId(1), FileId(10))],
+                "1.20.1",
+                ModLoaderType::Forge,
+                FileReleaseType::Release,
+            )
+            .await
+            .unwrap();
+        assert!(
+            matches!(&checks[0].status, UpdateStatus::Available(update)
+            if update.file.id == FileId(12) && update.changelog.is_none()),
+            "{checks:?}"
+        );
+        assert!(
+            server
+                .requests()
+                .iter()
+                .any(|request| request.path == "/mods/1/files/12/changelog")
+        );
+    }
+}